@@ -0,0 +1,93 @@
+use std::fmt::{Display, Formatter};
+
+use ipnet::IpNet;
+
+use crate::ip_range::{IpRange, to_bits};
+
+/// Number of IP addresses, wide enough for the whole IPv6 address space
+/// (2^128).
+#[derive(Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct AddrCount {
+    /// Count minus one, empty sets of addresses are never counted.
+    last: u128,
+}
+
+impl AddrCount {
+    pub fn of_range(range: &IpRange) -> Self {
+        Self {
+            last: to_bits(range.end()) - to_bits(range.start()),
+        }
+    }
+
+    pub fn of_net(net: &IpNet) -> Self {
+        Self {
+            last: to_bits(net.hostmask()),
+        }
+    }
+
+    /// Number of addresses usable by hosts, excluding IPv4 network and
+    /// broadcast addresses when the network is large enough to have them.
+    pub fn usable_hosts(net: &IpNet) -> Self {
+        let mut count = Self::of_net(net);
+        if let IpNet::V4(net) = net
+            && net.prefix_len() < 31
+        {
+            count.last -= 2;
+        }
+        count
+    }
+
+    /// Exact value, if it fits in an u128.
+    pub fn get(self) -> Option<u128> {
+        self.last.checked_add(1)
+    }
+
+    pub fn as_f64(self) -> f64 {
+        self.last as f64 + 1.0
+    }
+
+    /// Exponent of the count if it's a power of two.
+    pub fn log2(self) -> Option<u32> {
+        match self.get() {
+            Some(count) => count.is_power_of_two().then(|| count.trailing_zeros()),
+            None => Some(128),
+        }
+    }
+}
+
+impl Display for AddrCount {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.get() {
+            Some(count) => write!(f, "{count}"),
+            None => write!(f, "340282366920920463463374607431768211456"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AddrCount;
+
+    #[test]
+    fn count() {
+        let net = |net: &str| net.parse().unwrap();
+        assert_eq!(AddrCount::of_net(&net("10.0.0.0/24")).get(), Some(256));
+        assert_eq!(
+            AddrCount::usable_hosts(&net("10.0.0.0/24")).get(),
+            Some(254)
+        );
+        assert_eq!(AddrCount::usable_hosts(&net("10.0.0.0/31")).get(), Some(2));
+        assert_eq!(AddrCount::usable_hosts(&net("10.0.0.1/32")).get(), Some(1));
+        assert_eq!(AddrCount::usable_hosts(&net("::/64")).log2(), Some(64));
+        assert_eq!(AddrCount::of_net(&net("::/0")).get(), None);
+        assert_eq!(AddrCount::of_net(&net("::/0")).log2(), Some(128));
+        assert_eq!(
+            AddrCount::of_net(&net("::/0")).to_string(),
+            "340282366920920463463374607431768211456"
+        );
+        assert_eq!(
+            AddrCount::of_range(&"10.0.0.5-20".parse().unwrap()).log2(),
+            Some(4)
+        );
+    }
+}
@@ -5,28 +5,84 @@ use std::{
 };
 
 use anyhow::Error as AnyError;
+use either::Either;
 use ipnet::IpNet;
+use serde::{Deserialize, Deserializer, de};
+
+use crate::ip_range::IpRange;
 
 #[derive(Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Debug)]
+#[allow(clippy::enum_variant_names)]
 pub enum AddrOrNet {
     IpAddr(IpAddr),
     IpNet(IpNet),
+    IpRange(IpRange),
 }
 
-impl From<AddrOrNet> for IpNet {
-    fn from(input: AddrOrNet) -> Self {
-        match input {
-            AddrOrNet::IpAddr(addr) => IpNet::from(addr),
-            AddrOrNet::IpNet(net) => net,
+impl AddrOrNet {
+    /// Network equivalent of the input, if any (ranges may not be aligned).
+    pub fn net(self) -> Option<IpNet> {
+        match self {
+            AddrOrNet::IpAddr(addr) => Some(IpNet::from(addr)),
+            AddrOrNet::IpNet(net) => Some(net),
+            AddrOrNet::IpRange(_) => None,
+        }
+    }
+
+    /// Minimal list of networks covering the input.
+    pub fn nets(self) -> impl Iterator<Item = IpNet> {
+        match self {
+            AddrOrNet::IpRange(range) => Either::Left(range.nets()),
+            _ => Either::Right(self.net().into_iter()),
+        }
+    }
+
+    pub fn range(self) -> IpRange {
+        match self {
+            AddrOrNet::IpAddr(addr) => IpRange::from(addr),
+            AddrOrNet::IpNet(net) => IpRange::from(net),
+            AddrOrNet::IpRange(range) => range,
+        }
+    }
+
+    pub fn type_name(self) -> &'static str {
+        match self {
+            AddrOrNet::IpAddr(_) => "addr",
+            AddrOrNet::IpNet(_) => "net",
+            AddrOrNet::IpRange(_) => "range",
         }
     }
+
+    pub fn is_ipv4(self) -> bool {
+        self.range().start().is_ipv4()
+    }
+}
+
+impl From<IpAddr> for AddrOrNet {
+    fn from(addr: IpAddr) -> Self {
+        Self::IpAddr(addr)
+    }
+}
+
+impl From<IpNet> for AddrOrNet {
+    fn from(net: IpNet) -> Self {
+        Self::IpNet(net)
+    }
+}
+
+impl From<IpRange> for AddrOrNet {
+    fn from(range: IpRange) -> Self {
+        Self::IpRange(range)
+    }
 }
 
 impl FromStr for AddrOrNet {
     type Err = AnyError;
 
     fn from_str(input: &str) -> Result<Self, Self::Err> {
-        if input.contains('/') {
+        if input.contains('-') {
+            Ok(Self::IpRange(input.parse()?))
+        } else if input.contains('/') {
             Ok(Self::IpNet(input.parse()?))
         } else {
             Ok(Self::IpAddr(input.parse()?))
@@ -34,11 +90,21 @@ impl FromStr for AddrOrNet {
     }
 }
 
+impl<'de> Deserialize<'de> for AddrOrNet {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        FromStr::from_str(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
 impl Display for AddrOrNet {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             AddrOrNet::IpAddr(addr) => write!(f, "{addr}"),
             AddrOrNet::IpNet(net) => write!(f, "{net}"),
+            AddrOrNet::IpRange(range) => write!(f, "{range}"),
         }
     }
 }
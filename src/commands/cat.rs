@@ -1,6 +1,16 @@
-use crate::{addr_or_net::AddrOrNet, input::Input, source::Source};
+use crate::{
+    addr_or_net::AddrOrNet,
+    input::Input,
+    output::{self, Printer},
+    source::Source,
+};
 
-pub fn process_batch(sources: Vec<Source>, sort: bool, unique: bool) -> Result<(), anyhow::Error> {
+pub fn process_batch(
+    sources: Vec<Source>,
+    sort: bool,
+    unique: bool,
+    printer: &mut Printer,
+) -> Result<(), anyhow::Error> {
     let mut input = Input::<AddrOrNet>::Lazy(sources);
     if unique {
         input.unique()?;
@@ -10,7 +20,8 @@ pub fn process_batch(sources: Vec<Source>, sort: bool, unique: bool) -> Result<(
     }
 
     for value in input {
-        println!("{}", value?);
+        let value = value?;
+        printer.print(value, value, || output::input_record(value))?;
     }
 
     Ok(())
@@ -0,0 +1,56 @@
+use crate::{
+    addr_or_net::AddrOrNet,
+    input::Input,
+    output::{Printer, Value},
+    source::Source,
+    special_purpose,
+};
+
+pub fn process_batch(
+    sources: Vec<Source>,
+    sort: bool,
+    unique: bool,
+    printer: &mut Printer,
+) -> Result<(), anyhow::Error> {
+    let mut input = Input::<AddrOrNet>::Lazy(sources);
+    if unique {
+        input.unique()?;
+    }
+    if sort {
+        input.sort()?;
+    }
+
+    for value in input {
+        let value = value?;
+        let block = special_purpose::classify(value);
+        let text = match block {
+            Some(block) => format!(
+                "{value}: {} ({}, {})",
+                <&str>::from(block.scope),
+                block.name,
+                block.rfc
+            ),
+            // Inputs spanning several blocks or unassigned IPv6 space.
+            None => format!("{value}: unclassified"),
+        };
+        printer.print(value, text, || {
+            vec![
+                ("input", Value::string(value)),
+                (
+                    "scope",
+                    block.map_or(Value::Null, |b| Value::string(<&str>::from(b.scope))),
+                ),
+                ("name", block.map_or(Value::Null, |b| Value::string(b.name))),
+                ("rfc", block.map_or(Value::Null, |b| Value::string(b.rfc))),
+                (
+                    "globally_reachable",
+                    block
+                        .and_then(|b| b.globally_reachable)
+                        .map_or(Value::Null, Value::Bool),
+                ),
+            ]
+        })?;
+    }
+
+    Ok(())
+}
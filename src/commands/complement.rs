@@ -0,0 +1,97 @@
+use ipnet::{IpNet, Ipv4Net, Ipv6Net};
+
+use crate::{
+    addr_or_net::AddrOrNet,
+    ip_range::IpRange,
+    ip_set::IpSet,
+    output::{Printer, Value},
+    source::Source,
+};
+
+pub fn process(
+    sources: Vec<Source>,
+    universe: Vec<AddrOrNet>,
+    printer: &mut Printer,
+) -> Result<(), anyhow::Error> {
+    let set = IpSet::load(sources)?;
+    for net in complement(&set, universe).nets() {
+        printer.print(net, net, || vec![("network", Value::string(net))])?;
+    }
+
+    Ok(())
+}
+
+/// Part of the universe not covered by the set, the universe being the whole
+/// address space of each IP version present in the set if empty.
+fn complement(set: &IpSet, universe: Vec<AddrOrNet>) -> IpSet {
+    let universe = if universe.is_empty() {
+        let ranges = set.ranges();
+        [
+            ranges
+                .iter()
+                .any(|r| r.start().is_ipv4())
+                .then(|| IpNet::V4(Ipv4Net::default())),
+            ranges
+                .iter()
+                .any(|r| r.start().is_ipv6())
+                .then(|| IpNet::V6(Ipv6Net::default())),
+        ]
+        .into_iter()
+        .flatten()
+        .map(IpRange::from)
+        .collect::<IpSet>()
+    } else {
+        universe.into_iter().map(AddrOrNet::range).collect()
+    };
+    universe.difference(set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::complement;
+    use crate::{addr_or_net::AddrOrNet, ip_set::IpSet};
+
+    fn complement_of(set: &[&str], universe: &[&str]) -> Vec<String> {
+        let parse = |input: &&str| input.parse::<AddrOrNet>().unwrap();
+        complement(
+            &set.iter()
+                .map(|input| parse(input).range())
+                .collect::<IpSet>(),
+            universe.iter().map(parse).collect(),
+        )
+        .nets()
+        .map(|net| net.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn within() {
+        assert_eq!(
+            complement_of(&["10.0.0.0/9", "10.192.0.0/10"], &["10.0.0.0/8"]),
+            ["10.128.0.0/10"]
+        );
+        // Inputs outside the universe are ignored.
+        assert_eq!(
+            complement_of(&["10.0.0.0/24", "192.0.2.0/24"], &["10.0.0.0/23"]),
+            ["10.0.1.0/24"]
+        );
+        assert_eq!(
+            complement_of(&["10.0.0.0/8"], &["10.1.0.0/16"]),
+            Vec::<String>::new()
+        );
+        assert_eq!(
+            complement_of(&[], &["192.0.2.0-192.0.2.9"]),
+            ["192.0.2.0/29", "192.0.2.8/31"]
+        );
+    }
+
+    #[test]
+    fn whole_address_space() {
+        assert_eq!(complement_of(&["128.0.0.0/1"], &[]), ["0.0.0.0/1"]);
+        assert_eq!(
+            complement_of(&["0.0.0.0/1", "::/1"], &[]),
+            ["128.0.0.0/1", "8000::/1"]
+        );
+        assert!(complement_of(&[], &[]).is_empty());
+    }
+}
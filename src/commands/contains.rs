@@ -0,0 +1,132 @@
+use ipnet::IpNet;
+
+use crate::{
+    addr_or_net::AddrOrNet,
+    config::Config,
+    group,
+    input::Input,
+    ip_range::IpRange,
+    output::{self, Printer, Value},
+    prefix_trie::PrefixTrie,
+    source::Source,
+};
+
+/// Returns whether any input was selected.
+#[allow(clippy::too_many_arguments)]
+pub fn process_batch(
+    sources: Vec<Source>,
+    haystacks: Vec<Source>,
+    haystack_groups: Vec<String>,
+    mut configuration: Option<Config>,
+    quiet: bool,
+    invert: bool,
+    count: bool,
+    sort: bool,
+    unique: bool,
+    printer: &mut Printer,
+) -> Result<bool, anyhow::Error> {
+    let mut haystack = Haystack::new();
+    for entry in Input::<AddrOrNet>::Lazy(haystacks) {
+        haystack.insert(entry?.range());
+    }
+    for name in haystack_groups {
+        for entry in group::group_entries(&name, configuration.as_mut())? {
+            haystack.insert(entry.range());
+        }
+    }
+
+    let mut input = Input::<AddrOrNet>::Lazy(sources);
+    if unique {
+        input.unique()?;
+    }
+    if sort {
+        input.sort()?;
+    }
+
+    let mut matches = 0usize;
+    for value in input {
+        let value = value?;
+        if haystack.contains(value.range()) == invert {
+            continue;
+        }
+        matches += 1;
+        if quiet {
+            // Like grep, the first selected needle is enough to know the exit code.
+            break;
+        }
+        if !count {
+            printer.print(value, value, || output::input_record(value))?;
+        }
+    }
+    if count && !quiet {
+        printer.print_summary(matches, || vec![("count", Value::Number(matches as u128))])?;
+    }
+
+    Ok(matches > 0)
+}
+
+/// Haystack entries indexed by the smallest network covering them, the
+/// entries themselves being checked as ranges aren't aligned on networks.
+struct Haystack(PrefixTrie<IpRange>);
+
+impl Haystack {
+    fn new() -> Self {
+        Self(PrefixTrie::new())
+    }
+
+    fn insert(&mut self, entry: IpRange) {
+        let net = IpNet::new(entry.start(), entry.covering_prefix_len())
+            .expect("unexpected invalid prefix length")
+            .trunc();
+        self.0.insert(net, entry);
+    }
+
+    /// Same semantics as groups: the needle must be part of a single haystack
+    /// entry.
+    fn contains(&self, needle: IpRange) -> bool {
+        self.0
+            .containing(&needle)
+            .any(|(_, entry)| entry.contains(&needle))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Haystack;
+    use crate::addr_or_net::AddrOrNet;
+
+    #[test]
+    fn containment() {
+        let mut haystack = Haystack::new();
+        for entry in [
+            "10.0.0.0/25",
+            "10.0.0.128/25",
+            "192.0.2.0/24",
+            "198.51.100.7-198.51.100.20",
+            "2001:db8::/32",
+        ] {
+            haystack.insert(entry.parse::<AddrOrNet>().unwrap().range());
+        }
+        let contained =
+            |needle: &str| haystack.contains(needle.parse::<AddrOrNet>().unwrap().range());
+        // Nested.
+        assert!(contained("192.0.2.1"));
+        assert!(contained("192.0.2.16-192.0.2.31"));
+        assert!(contained("2001:db8:1::/48"));
+        // Equal.
+        assert!(contained("192.0.2.0/24"));
+        // Partial.
+        assert!(!contained("192.0.2.0/23"));
+        assert!(!contained("192.0.2.200-192.0.3.10"));
+        // Spanning adjacent entries.
+        assert!(!contained("10.0.0.0/24"));
+        assert!(!contained("10.0.0.127-10.0.0.128"));
+        // Unaligned range entry.
+        assert!(contained("198.51.100.8/29"));
+        assert!(contained("198.51.100.7-198.51.100.20"));
+        assert!(!contained("198.51.100.0/29"));
+        assert!(!contained("198.51.100.16/28"));
+        // Other IP version.
+        assert!(!contained("::ffff:192.0.2.1"));
+    }
+}
@@ -0,0 +1,81 @@
+use ipnet::IpNet;
+use itertools::Itertools;
+
+use crate::{
+    ip_set::IpSet,
+    output::{Printer, Value},
+    source::Source,
+};
+
+/// Returns whether the lists differ.
+pub fn process(
+    sources: Vec<Source>,
+    targets: Vec<Source>,
+    printer: &mut Printer,
+) -> Result<bool, anyhow::Error> {
+    let (old, new) = (IpSet::load(sources)?, IpSet::load(targets)?);
+
+    let mut changed = false;
+    for (sign, net) in changes(&old, &new) {
+        changed = true;
+        printer.print(net, format_args!("{sign}{net}"), || {
+            vec![
+                (
+                    "change",
+                    Value::string(if sign == '+' { "added" } else { "removed" }),
+                ),
+                ("network", Value::string(net)),
+            ]
+        })?;
+    }
+
+    Ok(changed)
+}
+
+/// Networks added ('+') and removed ('-') from one set to the other, in address
+/// order.
+fn changes(old: &IpSet, new: &IpSet) -> Vec<(char, IpNet)> {
+    new.difference(old)
+        .nets()
+        .map(|net| ('+', net))
+        .merge_by(old.difference(new).nets().map(|net| ('-', net)), |a, b| {
+            a.1 <= b.1
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::changes;
+    use crate::{ip_set::IpSet, source::Source};
+
+    #[test]
+    fn changes_between_lists() {
+        let set = |inputs: &[&str]| {
+            IpSet::load(inputs.iter().map(|i| Source::Arg(i.to_string())).collect()).unwrap()
+        };
+        let changes = |old: &[&str], new: &[&str]| {
+            changes(&set(old), &set(new))
+                .into_iter()
+                .map(|(sign, net)| format!("{sign}{net}"))
+                .collect::<Vec<_>>()
+        };
+        // Equal address space, whatever the notation.
+        assert!(changes(&["10.0.0.0/24"], &["10.0.0.0/25", "10.0.0.128/25"]).is_empty());
+        // Nested.
+        assert_eq!(
+            changes(&["10.0.0.0/24"], &["10.0.0.0/25"]),
+            ["-10.0.0.128/25"]
+        );
+        // Partial overlap.
+        assert_eq!(
+            changes(&["10.0.0.0/25"], &["10.0.0.64/26", "10.0.0.128/26"]),
+            ["-10.0.0.0/26", "+10.0.0.128/26"]
+        );
+        // Adjacent.
+        assert_eq!(
+            changes(&["10.0.0.0/25", "::/127"], &["10.0.0.128/25", "::/127"]),
+            ["-10.0.0.0/25", "+10.0.0.128/25"]
+        );
+    }
+}
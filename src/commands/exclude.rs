@@ -0,0 +1,31 @@
+use crate::{
+    config::Config,
+    group,
+    ip_set::IpSet,
+    output::{Printer, Value},
+    source::Source,
+};
+
+pub fn process(
+    sources: Vec<Source>,
+    exclusions: Vec<Source>,
+    exclusion_groups: Vec<String>,
+    mut configuration: Option<Config>,
+    printer: &mut Printer,
+) -> Result<(), anyhow::Error> {
+    let set = IpSet::load(sources)?;
+    let mut excluded = IpSet::load(exclusions)?.ranges().to_vec();
+    for name in exclusion_groups {
+        excluded.extend(
+            group::group_entries(&name, configuration.as_mut())?
+                .iter()
+                .map(|entry| entry.range()),
+        );
+    }
+
+    for net in set.difference(&excluded.into_iter().collect()).nets() {
+        printer.print(net, net, || vec![("network", Value::string(net))])?;
+    }
+
+    Ok(())
+}
@@ -1,19 +1,42 @@
-use std::sync::OnceLock;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, Mutex},
+};
 
-use evalexpr::{ContextWithMutableVariables, HashMapContext, Value};
-use ipnet::IpNet;
-use regex::Regex;
-use strum::{EnumIter, IntoEnumIterator, IntoStaticStr};
+use anyhow::{Context, bail};
+use evalexpr::{
+    ContextWithMutableFunctions, ContextWithMutableVariables, EvalexprError, EvalexprResult,
+    Function, HashMapContext, Node, Operator, Value,
+};
+use itertools::Itertools;
+use strum::{EnumIter, EnumString, IntoEnumIterator, IntoStaticStr};
 
-use crate::{addr_or_net::AddrOrNet, config::Config, group, input::Input, source::Source};
+use crate::{
+    addr_or_net::AddrOrNet,
+    config::Config,
+    group::{self, GroupMatch},
+    input::Input,
+    ip_range::IpRange,
+    output::{self, Printer},
+    prefix_trie::PrefixTrie,
+    source::Source,
+    special_purpose::{self, Scope},
+};
+
+/// Input the query is evaluated against before reading the actual ones.
+const SAMPLE_INPUT: &str = "192.0.2.0/24";
 
 pub fn process_batch(
     sources: Vec<Source>,
     query: String,
     mut configuration: Option<Config>,
+    group_match: GroupMatch,
     sort: bool,
     unique: bool,
+    printer: &mut Printer,
 ) -> Result<(), anyhow::Error> {
+    let mut query = Query::compile(&query, configuration.as_mut(), group_match)?;
+
     let mut input = Input::<AddrOrNet>::Lazy(sources);
     if unique {
         input.unique()?;
@@ -22,90 +45,398 @@ pub fn process_batch(
         input.sort()?;
     }
 
-    let requested_placeholders = Placeholder::requested(&query).collect::<Vec<_>>();
     for value in input {
         let value = value?;
+        if query.matches(value, configuration.as_mut())? {
+            printer.print(value, value, || output::input_record(value))?;
+        }
+    }
 
-        let mut context = HashMapContext::new();
-        for placeholder in &requested_placeholders {
-            context.set_value(
-                <&str>::from(placeholder).to_owned(),
-                placeholder.resolve(value, configuration.as_mut())?,
-            )?;
+    Ok(())
+}
+
+/// Expression parsed once and evaluated against each input.
+struct Query {
+    tree: Node,
+    placeholders: Vec<Placeholder>,
+    context: HashMapContext,
+    /// Input read by the membership functions.
+    current: Arc<Mutex<IpRange>>,
+    group_match: GroupMatch,
+}
+
+impl Query {
+    /// Parse the query, reporting syntax errors and unknown identifiers before
+    /// any input is read. A sample evaluation also catches errors not depending
+    /// on the input (e.g. invalid function arguments), but placeholders without
+    /// a value (e.g. `group` when none matches) can still make it fail later.
+    fn compile(
+        query: &str,
+        mut configuration: Option<&mut Config>,
+        group_match: GroupMatch,
+    ) -> Result<Self, anyhow::Error> {
+        let tree = evalexpr::build_operator_tree(query).context("invalid query")?;
+
+        let assigned = tree
+            .iter_write_variable_identifiers()
+            .collect::<HashSet<_>>();
+        let mut placeholders = Vec::new();
+        for identifier in tree.iter_read_variable_identifiers() {
+            match identifier.parse::<Placeholder>() {
+                Ok(placeholder) => {
+                    if !placeholders.contains(&placeholder) {
+                        placeholders.push(placeholder);
+                    }
+                }
+                Err(_) if assigned.contains(identifier) => {}
+                Err(_) => bail!(
+                    "unknown placeholder in query: {identifier} (available: {})",
+                    Placeholder::iter().map(<&str>::from).join(", ")
+                ),
+            }
         }
 
-        if evalexpr::eval_boolean_with_context_mut(&query, &mut context)? {
-            println!("{}", value);
+        if matches!(group_match, GroupMatch::All) && placeholders.contains(&Placeholder::Group) {
+            bail!("the group placeholder holds a single group, use groups to match all of them");
         }
+
+        // Groups are only loaded when used, and indexed once for lookups.
+        let group_tries = if tree.iter_function_identifiers().any(|f| f == "in_group") {
+            Some(
+                group::all_group_entries(configuration.as_deref_mut())?
+                    .into_iter()
+                    .map(|(name, entries)| {
+                        let mut trie = PrefixTrie::new();
+                        for net in entries.iter().flat_map(|entry| entry.nets()) {
+                            trie.insert(net, ());
+                        }
+                        (name, trie)
+                    })
+                    .collect(),
+            )
+        } else {
+            None
+        };
+
+        // Literal arguments of the membership functions are only parsed once.
+        let constants = tree
+            .iter()
+            .filter_map(|node| match node.operator() {
+                Operator::Const {
+                    value: Value::String(value),
+                } => Some((value.clone(), value.parse::<AddrOrNet>().ok()?.range())),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
+        let sample = SAMPLE_INPUT.parse::<AddrOrNet>()?;
+        let current = Arc::new(Mutex::new(sample.range()));
+        let mut context = HashMapContext::new();
+        set_functions(&mut context, &current, Arc::new(constants), group_tries)?;
+        let mut query = Self {
+            tree,
+            placeholders,
+            context,
+            current,
+            group_match,
+        };
+        query
+            .matches(sample, configuration)
+            .context("invalid query")?;
+        Ok(query)
     }
 
-    Ok(())
+    fn matches(
+        &mut self,
+        input: AddrOrNet,
+        mut configuration: Option<&mut Config>,
+    ) -> Result<bool, anyhow::Error> {
+        *self.current.lock().unwrap() = input.range();
+        // Values can't change type once set, and assignments must not leak to the
+        // next input.
+        self.context.clear_variables();
+        for placeholder in &self.placeholders {
+            self.context.set_value(
+                <&str>::from(placeholder).to_owned(),
+                placeholder.resolve(input, configuration.as_deref_mut(), self.group_match)?,
+            )?;
+        }
+        Ok(self.tree.eval_boolean_with_context_mut(&mut self.context)?)
+    }
 }
 
-#[derive(IntoStaticStr, EnumIter, Copy, Clone, Debug)]
+#[derive(EnumString, IntoStaticStr, EnumIter, Eq, PartialEq, Copy, Clone, Debug)]
 #[strum(serialize_all = "snake_case")]
-enum Placeholder {
+pub enum Placeholder {
     IpVersion,
     Type,
     Prefix,
     Group,
     Groups,
     Hosts,
+    Scope,
+    IsPrivate,
+    IsLoopback,
+    IsMulticast,
+    IsLinkLocal,
+    IsDocumentation,
+    IsGlobal,
+    /// Not globally routable: special-purpose (except globally reachable ones)
+    /// or unassigned space.
+    IsBogon,
 }
 
 impl Placeholder {
-    fn requested(query: &str) -> impl Iterator<Item = Placeholder> + '_ {
-        static CELL: OnceLock<Vec<(Placeholder, Regex)>> = OnceLock::new();
-        CELL.get_or_init(|| {
-            Placeholder::iter()
-                .map(|p| {
-                    (
-                        p,
-                        Regex::new(&format!(r#"\b{}\b"#, <&str>::from(p))).expect("invalid regex"),
-                    )
-                })
-                .collect()
-        })
-        .iter()
-        .filter_map(|(p, r)| r.is_match(query).then_some(*p))
-    }
-
-    fn resolve(
+    pub fn resolve(
         self,
         input: AddrOrNet,
         configuration: Option<&mut Config>,
+        group_match: GroupMatch,
     ) -> Result<Value, anyhow::Error> {
         Ok(match self {
-            Placeholder::IpVersion => Value::Int(match IpNet::from(input) {
-                IpNet::V4(_) => 4,
-                IpNet::V6(_) => 6,
-            }),
-            Placeholder::Type => Value::String(
-                match input {
-                    AddrOrNet::IpAddr(_) => "addr",
-                    AddrOrNet::IpNet(_) => "net",
-                }
-                .to_owned(),
-            ),
-            Placeholder::Prefix => Value::Int(IpNet::from(input).prefix_len() as i64),
+            Placeholder::IpVersion => Value::Int(if input.is_ipv4() { 4 } else { 6 }),
+            Placeholder::Type => Value::String(input.type_name().to_owned()),
+            // Prefix of the smallest network covering the range for ranges.
+            Placeholder::Prefix => Value::Int(input.range().covering_prefix_len() as i64),
             Placeholder::Group => {
-                match group::matching_groups(input, configuration)?
-                    .next()
-                    .transpose()?
-                {
+                match group::matching_groups(input, configuration, group_match)?.next() {
                     Some(group) => Value::String(group.to_owned()),
                     None => Value::Empty,
                 }
             }
             Placeholder::Groups => Value::Tuple(
-                group::matching_groups(input, configuration)?
-                    .map(|group| group.map(|group| Value::String(group.to_owned())))
-                    .collect::<Result<_, _>>()?,
+                group::matching_groups(input, configuration, GroupMatch::All)?
+                    .map(|group| Value::String(group.to_owned()))
+                    .collect(),
             ),
             Placeholder::Hosts => {
-                let net = IpNet::from(input);
-                Value::Int(2u128.pow((net.max_prefix_len() - net.prefix_len()) as u32) as i64)
+                let count = input.range().size();
+                match count.get().and_then(|c| i64::try_from(c).ok()) {
+                    Some(count) => Value::Int(count),
+                    None => Value::Float(count.as_f64()),
+                }
+            }
+            Placeholder::Scope => match special_purpose::classify(input) {
+                Some(block) => Value::String(<&str>::from(block.scope).to_owned()),
+                None => Value::Empty,
+            },
+            Placeholder::IsPrivate => {
+                Value::Boolean(has_scope(input, &[Scope::Private, Scope::UniqueLocal]))
+            }
+            Placeholder::IsLoopback => Value::Boolean(has_scope(input, &[Scope::Loopback])),
+            Placeholder::IsMulticast => Value::Boolean(has_scope(input, &[Scope::Multicast])),
+            Placeholder::IsLinkLocal => Value::Boolean(has_scope(input, &[Scope::LinkLocal])),
+            Placeholder::IsDocumentation => {
+                Value::Boolean(has_scope(input, &[Scope::Documentation]))
             }
+            Placeholder::IsGlobal => Value::Boolean(
+                special_purpose::classify(input)
+                    .is_some_and(|block| block.globally_reachable == Some(true)),
+            ),
+            Placeholder::IsBogon => Value::Boolean(
+                special_purpose::classify(input)
+                    .is_none_or(|block| block.globally_reachable != Some(true)),
+            ),
         })
     }
 }
+
+/// Membership tests against the current input: `in(net)`, `overlaps(net)` and
+/// `in_group(name)`.
+fn set_functions(
+    context: &mut HashMapContext,
+    current: &Arc<Mutex<IpRange>>,
+    constants: Arc<HashMap<String, IpRange>>,
+    group_tries: Option<HashMap<String, PrefixTrie<()>>>,
+) -> Result<(), anyhow::Error> {
+    let (range, known) = (current.clone(), constants.clone());
+    context.set_function(
+        "in".to_owned(),
+        Function::new(move |argument| {
+            Ok(Value::Boolean(
+                to_range(argument, &known)?.contains(&range.lock().unwrap()),
+            ))
+        }),
+    )?;
+    let (range, known) = (current.clone(), constants);
+    context.set_function(
+        "overlaps".to_owned(),
+        Function::new(move |argument| {
+            Ok(Value::Boolean(
+                to_range(argument, &known)?.overlaps(&range.lock().unwrap()),
+            ))
+        }),
+    )?;
+    if let Some(group_tries) = group_tries {
+        let range = current.clone();
+        context.set_function(
+            "in_group".to_owned(),
+            Function::new(move |argument| {
+                let name = argument.as_string()?;
+                let trie = group_tries.get(&name).ok_or_else(|| {
+                    EvalexprError::CustomMessage(format!("unknown group: {name}"))
+                })?;
+                // Same semantics as groups: the input must be part of a single network.
+                Ok(Value::Boolean(
+                    trie.containing(&range.lock().unwrap()).next().is_some(),
+                ))
+            }),
+        )?;
+    }
+    Ok(())
+}
+
+/// Range of a function argument, parsed beforehand if it is a literal.
+fn to_range(argument: &Value, constants: &HashMap<String, IpRange>) -> EvalexprResult<IpRange> {
+    let Value::String(argument) = argument else {
+        return Err(EvalexprError::expected_string(argument.clone()));
+    };
+    if let Some(range) = constants.get(argument) {
+        return Ok(*range);
+    }
+    argument
+        .parse::<AddrOrNet>()
+        .map(AddrOrNet::range)
+        .map_err(|e| EvalexprError::CustomMessage(format!("invalid argument {argument:?}: {e}")))
+}
+
+fn has_scope(input: AddrOrNet, scopes: &[Scope]) -> bool {
+    special_purpose::classify(input).is_some_and(|block| scopes.contains(&block.scope))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Query;
+    use crate::{config::Config, group::GroupMatch};
+
+    fn selected<'a>(
+        query: &str,
+        mut configuration: Option<&mut Config>,
+        inputs: &[&'a str],
+    ) -> Vec<&'a str> {
+        let mut query =
+            Query::compile(query, configuration.as_deref_mut(), GroupMatch::First).unwrap();
+        inputs
+            .iter()
+            .copied()
+            .filter(|input| {
+                query
+                    .matches(input.parse().unwrap(), configuration.as_deref_mut())
+                    .unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn classification() {
+        let inputs = [
+            "8.8.8.8",
+            "10.0.0.1",
+            "fd00::1",
+            "192.0.0.9",
+            "240.0.0.1",
+            "::1",
+            "2001:4860::8888",
+        ];
+        assert_eq!(
+            selected("is_private", None, &inputs),
+            ["10.0.0.1", "fd00::1"]
+        );
+        assert_eq!(
+            selected("is_global", None, &inputs),
+            ["8.8.8.8", "192.0.0.9", "2001:4860::8888"]
+        );
+        // Public addresses are never bogons.
+        assert_eq!(
+            selected("is_bogon", None, &inputs),
+            ["10.0.0.1", "fd00::1", "240.0.0.1", "::1"]
+        );
+        assert_eq!(selected("is_loopback", None, &inputs), ["::1"]);
+        assert_eq!(
+            selected(r#"scope == "unique-local""#, None, &inputs),
+            ["fd00::1"]
+        );
+        assert_eq!(
+            selected("ip_version == 6 && prefix == 128", None, &inputs),
+            ["fd00::1", "::1", "2001:4860::8888"]
+        );
+    }
+
+    #[test]
+    fn membership() {
+        let mut configuration = toml::from_str::<Config>(
+            r#"
+            [[groups]]
+            name = "office"
+            nets = ["10.1.0.0/16", "10.2.0.1-10.2.0.9"]
+            "#,
+        )
+        .unwrap();
+        let inputs = [
+            "10.1.2.3",
+            "10.1.0.0/15",
+            "10.2.0.4-10.2.0.7",
+            "10.2.0.8/29",
+            "192.0.2.1",
+        ];
+        assert_eq!(
+            selected(r#"in("10.0.0.0/8")"#, None, &inputs),
+            [
+                "10.1.2.3",
+                "10.1.0.0/15",
+                "10.2.0.4-10.2.0.7",
+                "10.2.0.8/29"
+            ]
+        );
+        assert_eq!(
+            selected(r#"in("10.1.0.0-10.1.255.255")"#, None, &inputs),
+            ["10.1.2.3"]
+        );
+        assert_eq!(
+            selected(r#"overlaps("10.1.255.0/24")"#, None, &inputs),
+            ["10.1.0.0/15"]
+        );
+        // Same semantics as groups: the input must be part of a single network.
+        assert_eq!(
+            selected(r#"in_group("office")"#, Some(&mut configuration), &inputs),
+            ["10.1.2.3", "10.2.0.4-10.2.0.7"]
+        );
+        assert!(
+            Query::compile(
+                r#"in_group("unknown")"#,
+                Some(&mut configuration),
+                GroupMatch::First
+            )
+            .is_err()
+        );
+        assert!(Query::compile(r#"in_group("office")"#, None, GroupMatch::First).is_err());
+    }
+
+    #[test]
+    fn compile() {
+        let compile = |query: &str| Query::compile(query, None, GroupMatch::First);
+        assert!(compile("is_private &&").is_err());
+        assert!(compile("is_privat").is_err());
+        assert!(compile(r#"in("10.0.0.0/33")"#).is_err());
+        assert!(Query::compile("group == \"office\"", None, GroupMatch::All).is_err());
+        // Assigned variables aren't placeholders.
+        assert_eq!(
+            selected(
+                "private = is_private; private",
+                None,
+                &["10.0.0.1", "8.8.8.8"]
+            ),
+            ["10.0.0.1"]
+        );
+        // Assignments don't leak to the next input, where the value would have
+        // another type.
+        assert_eq!(
+            selected(
+                r#"kind = if(is_private, 1, "public"); kind == 1"#,
+                None,
+                &["10.0.0.1", "8.8.8.8", "10.0.0.2"]
+            ),
+            ["10.0.0.1", "10.0.0.2"]
+        );
+    }
+}
@@ -1,51 +1,115 @@
+use std::{cmp::Reverse, collections::HashMap, fmt::Write};
+
 use anyhow::bail;
+use ipnet::IpNet;
 use itertools::Itertools;
 
-use crate::{addr_or_net::AddrOrNet, config::Config, group, input::Input, source::Source};
+use crate::{
+    addr_or_net::AddrOrNet,
+    config::Config,
+    group::{self, GroupMatch},
+    input::Input,
+    output::{Printer, Value},
+    source::Source,
+};
 
+#[allow(clippy::too_many_arguments)]
 pub fn process_batch(
     sources: Vec<Source>,
     mut configuration: Option<Config>,
     exit_no_match: bool,
+    group_match: GroupMatch,
+    annotate: bool,
+    default_label: Option<String>,
+    summary: Option<Summary>,
     sort: bool,
     unique: bool,
+    printer: &mut Printer,
 ) -> Result<(), anyhow::Error> {
+    if let Some(summary) = summary {
+        let mut input = Input::<AddrOrNet>::Lazy(sources);
+        if unique {
+            input.unique()?;
+        }
+        return summarize(input, configuration, group_match, summary, sort, printer);
+    }
+
+    if annotate {
+        // One line per input, so inputs are sorted rather than groups.
+        let mut input = Input::<AddrOrNet>::Lazy(sources);
+        if unique {
+            input.unique()?;
+        }
+        if sort {
+            input.sort()?;
+        }
+        for value in input {
+            let value = value?;
+            let label = annotation(
+                value,
+                configuration.as_mut(),
+                group_match,
+                exit_no_match,
+                default_label.as_deref(),
+            )?;
+            printer.print(
+                value,
+                format_args!("{value}\t{}", label.as_deref().unwrap_or_default()),
+                || {
+                    vec![
+                        ("input", Value::string(value)),
+                        ("group", label.clone().map_or(Value::Null, Value::String)),
+                    ]
+                },
+            )?;
+        }
+        return Ok(());
+    }
+
     let input = Input::<AddrOrNet>::Lazy(sources);
     if sort || unique {
         let mut groups = Vec::new();
         for value in input {
             let value = value?;
-            match group::matching_groups(value, configuration.as_mut())?
-                .next()
-                .transpose()?
-            {
-                Some(group) => groups.push(group.to_owned()),
-                None => {
-                    if exit_no_match {
-                        bail!("no group found for {}", value);
-                    }
+            let len = groups.len();
+            groups.extend(
+                group::matching_groups(value, configuration.as_mut(), group_match)?
+                    .map(|group| (value, group.to_owned())),
+            );
+            if groups.len() == len {
+                match &default_label {
+                    Some(label) => groups.push((value, label.clone())),
+                    None if exit_no_match => bail!("no group found for {}", value),
+                    None => {}
                 }
             }
         }
         if sort {
-            groups.sort();
+            groups.sort_by(|(_, a), (_, b)| a.cmp(b));
         }
         if unique {
-            groups = groups.into_iter().unique().collect();
+            groups = groups
+                .into_iter()
+                .unique_by(|(_, group)| group.clone())
+                .collect();
+        }
+        for (value, group) in groups {
+            print(printer, value, &group)?;
         }
-        println!("{}", groups.join("\n"));
     } else {
         for value in input {
             let value = value?;
-            match group::matching_groups(value, configuration.as_mut())?
-                .next()
-                .transpose()?
-            {
-                Some(group) => println!("{group}"),
-                None => {
-                    if exit_no_match {
-                        bail!("no group found for {}", value);
-                    }
+            let mut found = false;
+            // Each matching group is a separate result.
+            for group in group::matching_groups(value, configuration.as_mut(), group_match)? {
+                found = true;
+                print(printer, value, group)?;
+            }
+            if !found {
+                match &default_label {
+                    Some(label) => print(printer, value, label)?,
+                    None if exit_no_match => bail!("no group found for {}", value),
+                    None => {}
                 }
             }
         }
@@ -53,3 +117,263 @@ pub fn process_batch(
 
     Ok(())
 }
+
+/// Matching groups of an input joined by commas, or the default label if none
+/// matches.
+fn annotation(
+    value: AddrOrNet,
+    configuration: Option<&mut Config>,
+    group_match: GroupMatch,
+    exit_no_match: bool,
+    default_label: Option<&str>,
+) -> Result<Option<String>, anyhow::Error> {
+    let groups = group::matching_groups(value, configuration, group_match)?.join(",");
+    if !groups.is_empty() {
+        return Ok(Some(groups));
+    }
+    if exit_no_match {
+        bail!("no group found for {}", value);
+    }
+    Ok(default_label.map(str::to_owned))
+}
+
+/// Summary mode options.
+#[derive(Copy, Clone, Debug)]
+pub struct Summary {
+    pub percent: bool,
+}
+
+/// Count inputs per group and per network within each group, in
+/// configuration order unless sorted by decreasing count.
+fn summarize(
+    input: Input<AddrOrNet>,
+    mut configuration: Option<Config>,
+    group_match: GroupMatch,
+    summary: Summary,
+    sort: bool,
+    printer: &mut Printer,
+) -> Result<(), anyhow::Error> {
+    let Counts {
+        total,
+        unmatched,
+        groups: mut counts,
+    } = count(input, configuration.as_mut(), group_match)?;
+
+    let mut groups = configuration
+        .and_then(|c| c.groups)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|group| group.name)
+        .unique()
+        .filter_map(|name| {
+            let (count, nets) = counts.remove(&name)?;
+            let mut nets = nets.into_iter().collect::<Vec<_>>();
+            if sort {
+                nets.sort_by_key(|&(net, count)| (Reverse(count), net));
+            } else {
+                nets.sort_unstable();
+            }
+            Some((name, count, nets))
+        })
+        .collect::<Vec<_>>();
+    if sort {
+        groups.sort_by_key(|(_, count, _)| Reverse(*count));
+    }
+
+    let rows = groups
+        .into_iter()
+        .flat_map(|(name, count, nets)| {
+            let group = Some(name);
+            [(group.clone(), None, count)].into_iter().chain(
+                nets.into_iter()
+                    .map(move |(net, count)| (group.clone(), Some(net), count)),
+            )
+        })
+        .chain((unmatched > 0).then_some((None, None, unmatched)))
+        .collect::<Vec<_>>();
+    let label = |group: &Option<String>, net: &Option<IpNet>| match (group, net) {
+        (_, Some(net)) => format!("  {net}"),
+        (Some(group), None) => group.clone(),
+        (None, None) => "(unmatched)".to_owned(),
+    };
+    let label_width = rows
+        .iter()
+        .map(|(group, net, _)| label(group, net).len())
+        .max()
+        .unwrap_or(0);
+    let count_width = rows
+        .iter()
+        .map(|(_, _, count)| count.to_string().len())
+        .max()
+        .unwrap_or(0);
+    for (group, net, count) in rows {
+        // Percentage of all the inputs (the sum exceeds 100% when matching all groups).
+        let percent = count as f64 * 100.0 / total as f64;
+        let mut text = format!(
+            "{:label_width$}  {count:>count_width$}",
+            label(&group, &net)
+        );
+        if summary.percent {
+            write!(text, "  {percent:5.1}%").unwrap();
+        }
+        printer.print_summary(text, || {
+            let mut record = vec![
+                ("group", group.clone().map_or(Value::Null, Value::String)),
+                ("network", net.map_or(Value::Null, Value::string)),
+                ("count", Value::Number(count as u128)),
+            ];
+            if summary.percent {
+                record.push(("percent", Value::Float((percent * 100.0).round() / 100.0)));
+            }
+            record
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Inputs counted per group and per network within each group.
+#[derive(Default, Debug)]
+struct Counts {
+    total: usize,
+    unmatched: usize,
+    groups: HashMap<String, (usize, HashMap<IpNet, usize>)>,
+}
+
+fn count(
+    input: impl IntoIterator<Item = Result<AddrOrNet, anyhow::Error>>,
+    mut configuration: Option<&mut Config>,
+    group_match: GroupMatch,
+) -> Result<Counts, anyhow::Error> {
+    let mut counts = Counts::default();
+    for value in input {
+        let value = value?;
+        counts.total += 1;
+        let mut found = false;
+        for (group, net) in
+            group::matching_group_nets(value, configuration.as_deref_mut(), group_match)?
+        {
+            found = true;
+            let (count, nets) = counts.groups.entry(group.to_owned()).or_default();
+            *count += 1;
+            *nets.entry(net).or_default() += 1;
+        }
+        if !found {
+            counts.unmatched += 1;
+        }
+    }
+    Ok(counts)
+}
+
+fn print(printer: &mut Printer, value: AddrOrNet, group: &str) -> Result<(), anyhow::Error> {
+    printer.print(value, group, || {
+        vec![
+            ("input", Value::string(value)),
+            ("group", Value::string(group)),
+        ]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{annotation, count};
+    use crate::{config::Config, group::GroupMatch};
+
+    #[test]
+    fn annotations() {
+        let mut configuration = toml::from_str::<Config>(
+            r#"
+            [[groups]]
+            name = "corp"
+            nets = ["10.0.0.0/8"]
+            [[groups]]
+            name = "dc1"
+            nets = ["10.1.0.0/16"]
+            "#,
+        )
+        .unwrap();
+        let mut annotation = |input: &str, group_match, exit_no_match, default_label| {
+            annotation(
+                input.parse().unwrap(),
+                Some(&mut configuration),
+                group_match,
+                exit_no_match,
+                default_label,
+            )
+        };
+
+        let label = |result: Result<Option<String>, _>| result.unwrap();
+        assert_eq!(
+            label(annotation("10.1.2.3", GroupMatch::All, false, None)).as_deref(),
+            Some("corp,dc1")
+        );
+        assert_eq!(
+            label(annotation("10.1.2.3", GroupMatch::First, false, None)).as_deref(),
+            Some("corp")
+        );
+        assert_eq!(
+            label(annotation(
+                "10.1.2.3",
+                GroupMatch::MostSpecific,
+                false,
+                None
+            ))
+            .as_deref(),
+            Some("dc1")
+        );
+        assert_eq!(
+            label(annotation(
+                "192.0.2.1",
+                GroupMatch::All,
+                false,
+                Some("none")
+            ))
+            .as_deref(),
+            Some("none")
+        );
+        assert_eq!(
+            label(annotation("192.0.2.1", GroupMatch::All, false, None)),
+            None
+        );
+        assert!(annotation("192.0.2.1", GroupMatch::All, true, None).is_err());
+    }
+
+    #[test]
+    fn counts() {
+        let mut configuration = toml::from_str::<Config>(
+            r#"
+            [[groups]]
+            name = "corp"
+            nets = ["10.0.0.0/8"]
+            [[groups]]
+            name = "dc1"
+            nets = ["10.1.0.0/16", "10.1.2.0/24"]
+            "#,
+        )
+        .unwrap();
+        let inputs = ["10.1.2.3", "10.1.2.4", "10.1.3.1", "10.2.0.1", "192.0.2.1"];
+        let mut count = |group_match| {
+            count(
+                inputs.iter().map(|input| input.parse()),
+                Some(&mut configuration),
+                group_match,
+            )
+            .unwrap()
+        };
+
+        let counts = count(GroupMatch::First);
+        assert_eq!((counts.total, counts.unmatched), (5, 1));
+        assert_eq!(counts.groups.len(), 1);
+        assert_eq!(counts.groups["corp"].0, 4);
+        assert_eq!(counts.groups["corp"].1[&"10.0.0.0/8".parse().unwrap()], 4);
+
+        let counts = count(GroupMatch::All);
+        assert_eq!((counts.total, counts.unmatched), (5, 1));
+        assert_eq!(counts.groups["corp"].0, 4);
+        // Counted under the most specific network of the group.
+        let dc1 = &counts.groups["dc1"];
+        assert_eq!(dc1.0, 3);
+        assert_eq!(dc1.1[&"10.1.2.0/24".parse().unwrap()], 2);
+        assert_eq!(dc1.1[&"10.1.0.0/16".parse().unwrap()], 1);
+    }
+}
@@ -0,0 +1,217 @@
+use ipnet::IpNet;
+use serde::Serialize;
+
+use crate::{
+    config::{Config, GroupSource},
+    group,
+    ip_range::IpRange,
+    ip_set::IpSet,
+    output::{Printer, Value},
+};
+
+pub fn list(mut configuration: Option<Config>, printer: &mut Printer) -> Result<(), anyhow::Error> {
+    let rows = describe(configuration.as_mut())?;
+
+    let name_width = rows.iter().map(|row| row.name.len()).max().unwrap_or(0);
+    let count_width = rows
+        .iter()
+        .map(|row| row.networks.to_string().len())
+        .max()
+        .unwrap_or(0);
+    for row in rows {
+        let text = format!(
+            "{:name_width$}  {:>count_width$}  {:7}  {}",
+            row.name,
+            row.networks,
+            row.source,
+            row.origin.as_deref().unwrap_or_default()
+        );
+        printer.print_summary(text.trim_end(), || {
+            vec![
+                ("name", Value::String(row.name.clone())),
+                ("source", Value::string(row.source)),
+                (
+                    "origin",
+                    row.origin.clone().map_or(Value::Null, Value::String),
+                ),
+                ("networks", Value::Number(row.networks as u128)),
+            ]
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Group as listed.
+#[derive(PartialEq, Debug)]
+struct Description {
+    name: String,
+    source: &'static str,
+    /// File path or command the networks come from.
+    origin: Option<String>,
+    networks: usize,
+}
+
+fn describe(configuration: Option<&mut Config>) -> Result<Vec<Description>, anyhow::Error> {
+    let mut rows = Vec::new();
+    for group in group::groups(configuration)? {
+        // Described before loading, which replaces the source with its networks.
+        let (source, origin) = match &group.source {
+            GroupSource::Raw { .. } => ("raw", None),
+            GroupSource::File { file } => ("file", Some(file.display().to_string())),
+            GroupSource::Command { command, .. } => ("command", Some(command.clone())),
+        };
+        rows.push(Description {
+            name: group.name.clone(),
+            source,
+            origin,
+            networks: group
+                .source
+                .load()?
+                .iter()
+                .map(|entry| entry.nets().count())
+                .sum(),
+        });
+    }
+    Ok(rows)
+}
+
+pub fn show(
+    name: &str,
+    merge: bool,
+    mut configuration: Option<Config>,
+    printer: &mut Printer,
+) -> Result<(), anyhow::Error> {
+    for net in networks(name, merge, configuration.as_mut())? {
+        printer.print(net, net, || vec![("network", Value::string(net))])?;
+    }
+
+    Ok(())
+}
+
+fn networks(
+    name: &str,
+    merge: bool,
+    configuration: Option<&mut Config>,
+) -> Result<Vec<IpNet>, anyhow::Error> {
+    let nets = group::group_entries(name, configuration)?
+        .iter()
+        .flat_map(|entry| entry.nets());
+    Ok(if merge {
+        nets.map(IpRange::from).collect::<IpSet>().nets().collect()
+    } else {
+        nets.collect()
+    })
+}
+
+/// Snapshot of the groups, loadable as a configuration.
+#[derive(Serialize, Debug)]
+struct Snapshot {
+    groups: Vec<RawGroup>,
+}
+
+#[derive(Serialize, Debug)]
+struct RawGroup {
+    name: String,
+    nets: Vec<String>,
+}
+
+pub fn export(
+    mut configuration: Option<Config>,
+    printer: &mut Printer,
+) -> Result<(), anyhow::Error> {
+    printer.print_document(|| snapshot(configuration.as_mut()))
+}
+
+fn snapshot(configuration: Option<&mut Config>) -> Result<Snapshot, anyhow::Error> {
+    let mut groups = Vec::new();
+    for group in group::groups(configuration)? {
+        groups.push(RawGroup {
+            name: group.name.clone(),
+            nets: group
+                .source
+                .load()?
+                .iter()
+                .map(|entry| entry.to_string())
+                .collect(),
+        });
+    }
+    Ok(Snapshot { groups })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Description, describe, networks, snapshot};
+    use crate::config::Config;
+
+    fn configuration() -> Config {
+        toml::from_str(
+            r#"
+            [[groups]]
+            name = "dc1"
+            nets = ["10.1.0.0/25", "10.1.0.128/25", "10.1.2.0-10.1.2.2"]
+            [[groups]]
+            name = "cmd"
+            command = "echo 192.0.2.0/24"
+            shell = "sh"
+            "#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn list() {
+        assert_eq!(
+            describe(Some(&mut configuration())).unwrap(),
+            [
+                Description {
+                    name: "dc1".to_owned(),
+                    source: "raw",
+                    origin: None,
+                    networks: 4,
+                },
+                Description {
+                    name: "cmd".to_owned(),
+                    source: "command",
+                    origin: Some("echo 192.0.2.0/24".to_owned()),
+                    networks: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn show() {
+        let mut configuration = configuration();
+        let mut show = |merge| {
+            networks("dc1", merge, Some(&mut configuration))
+                .unwrap()
+                .into_iter()
+                .map(|net| net.to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            show(false),
+            ["10.1.0.0/25", "10.1.0.128/25", "10.1.2.0/31", "10.1.2.2/32"]
+        );
+        assert_eq!(show(true), ["10.1.0.0/24", "10.1.2.0/31", "10.1.2.2/32"]);
+        assert!(networks("unknown", false, Some(&mut configuration)).is_err());
+    }
+
+    #[test]
+    fn export() {
+        let exported = toml::to_string(&snapshot(Some(&mut configuration())).unwrap()).unwrap();
+        // Reloaded as is, every source being raw networks.
+        let mut reloaded = toml::from_str::<Config>(&exported).unwrap();
+        let snapshot = snapshot(Some(&mut reloaded)).unwrap();
+        assert_eq!(toml::to_string(&snapshot).unwrap(), exported);
+        assert_eq!(
+            describe(Some(&mut reloaded))
+                .unwrap()
+                .iter()
+                .map(|group| (group.source, group.networks))
+                .collect::<Vec<_>>(),
+            [("raw", 4), ("raw", 1)]
+        );
+    }
+}
@@ -4,32 +4,40 @@ use either::Either;
 use ipnet::IpNet;
 use itertools::Itertools;
 
-use crate::{input::Input, source::Source};
+use crate::{
+    input::Input,
+    output::{Printer, Value},
+    source::Source,
+};
 
 pub fn process_batch(
     sources: Vec<Source>,
     all: bool,
     sort: bool,
     unique: bool,
+    printer: &mut Printer,
 ) -> Result<(), anyhow::Error> {
     let input = Input::<IpNet>::Lazy(sources);
     if sort || unique {
         let mut hosts = input
             .into_iter()
-            .map(|net| net.map(|net| process_single(net, all)))
+            .map(|net| net.map(|net| process_single(net, all).map(move |host| (net, host))))
             .flatten_ok()
             .collect::<Result<Vec<_>, _>>()?;
         if sort {
-            hosts.sort();
+            hosts.sort_by_key(|(_, host)| *host);
         }
         if unique {
-            hosts = hosts.into_iter().unique().collect();
+            hosts = hosts.into_iter().unique_by(|(_, host)| *host).collect();
+        }
+        for (net, host) in hosts {
+            print(printer, net, host)?;
         }
-        println!("{}", hosts.iter().join("\n"));
     } else {
         for net in input {
-            for host in process_single(net?, all) {
-                println!("{host}");
+            let net = net?;
+            for host in process_single(net, all) {
+                print(printer, net, host)?;
             }
         }
     }
@@ -48,3 +56,9 @@ fn process_single(net: IpNet, all: bool) -> impl Iterator<Item = IpAddr> {
         Either::Right(net.hosts())
     }
 }
+
+fn print(printer: &mut Printer, net: IpNet, host: IpAddr) -> Result<(), anyhow::Error> {
+    printer.print(host, host, || {
+        vec![("input", Value::string(net)), ("host", Value::string(host))]
+    })
+}
@@ -1,9 +1,23 @@
-use std::{fmt, fmt::Write, net::IpAddr};
+use std::{fmt::Write, net::IpAddr, str::FromStr};
 
+use anyhow::bail;
 use ipnet::IpNet;
 use itertools::Itertools;
+use serde::{Deserialize, Deserializer, de};
+use strum::{EnumIter, EnumString, IntoEnumIterator, IntoStaticStr};
+use terminal_size::Width;
 
-use crate::{auto_net::AutoNet, input::Input, source::Source};
+use crate::{
+    addr_count::AddrCount,
+    addr_or_net::AddrOrNet,
+    config::Config,
+    input::Input,
+    output::{Printer, Value},
+    source::Source,
+    special_purpose,
+};
+
+const COLUMN_SEPARATOR: &str = "  ";
 
 const DEFAULT_IPV4_FIELDS: &[Field] = &[
     Field::Address,
@@ -32,14 +46,30 @@ const DEFAULT_IPV6_FIELDS: &[Field] = &[
     Field::BinaryAddress,
     Field::BinaryNetworkMaskAddress,
 ];
+const DEFAULT_RANGE_FIELDS: &[Field] = &[
+    Field::First,
+    Field::Last,
+    Field::HostsCount,
+    Field::Networks,
+];
 
+#[allow(clippy::too_many_arguments)]
 pub fn process_batch(
     sources: Vec<Source>,
+    fields: Vec<Field>,
+    configuration: Option<Config>,
     padding: bool,
+    table: Option<Table>,
     sort: bool,
     unique: bool,
+    printer: &mut Printer,
 ) -> Result<(), anyhow::Error> {
-    let mut input = Input::<AutoNet>::Lazy(sources);
+    if table.is_some() && !printer.is_text() {
+        bail!("table layout is only available for text output");
+    }
+    let fields = FieldSelection::new(fields, configuration, !printer.is_text());
+
+    let mut input = Input::<AddrOrNet>::Lazy(sources);
     if unique {
         input.unique()?;
     }
@@ -47,96 +77,333 @@ pub fn process_batch(
         input.sort()?;
     }
 
-    #[allow(unstable_name_collisions)]
-    for value in input
-        .into_iter()
-        .map(|addr| addr.map(|addr| process(addr.0, padding)))
-        .intersperse_with(|| Ok(String::new()))
-    {
-        println!("{}", value?);
+    if let Some(table) = table {
+        let inputs = input.into_iter().collect::<Result<Vec<_>, _>>()?;
+        print_table(&inputs, &fields.columns(&inputs), padding, table);
+        return Ok(());
+    }
+
+    for (index, input) in input.into_iter().enumerate() {
+        let input = input?;
+        let fields = fields.get(input);
+        // Blocks are separated by an empty line.
+        let separator = if index == 0 { "" } else { "\n" };
+        printer.print(
+            input,
+            format_args!("{separator}{}", process(input, fields, padding)),
+            || {
+                [("input", Value::string(input))]
+                    .into_iter()
+                    .chain(
+                        fields
+                            .iter()
+                            // Same columns for every input, whatever its type.
+                            .map(|field| {
+                                (
+                                    <&str>::from(field),
+                                    field.value(input).unwrap_or(Value::Null),
+                                )
+                            }),
+                    )
+                    .collect()
+            },
+        )?;
     }
 
     Ok(())
 }
 
-fn process(addr: IpNet, padding: bool) -> String {
-    let fields = match addr {
-        IpNet::V4(_) => DEFAULT_IPV4_FIELDS,
-        IpNet::V6(_) => DEFAULT_IPV6_FIELDS,
+/// Table layout options.
+#[derive(Copy, Clone, Debug)]
+pub struct Table {
+    pub header: bool,
+}
+
+fn print_table(inputs: &[AddrOrNet], columns: &[Field], padding: bool, table: Table) {
+    let header = table.header.then(|| {
+        columns
+            .iter()
+            .map(|field| field.label().to_owned())
+            .collect()
+    });
+    let rows = header
+        .into_iter()
+        .chain(inputs.iter().map(|&input| {
+            columns
+                .iter()
+                .map(|field| field.text_value(input).unwrap_or_default())
+                .collect::<Vec<_>>()
+        }))
+        .collect::<Vec<_>>();
+    if !padding {
+        for row in rows {
+            println!("{}", row.join("\t"));
+        }
+        return;
+    }
+
+    let column_width = |i: usize, width: fn(&str) -> usize| {
+        rows.iter().map(|row| width(&row[i])).max().unwrap_or(0)
     };
-    let mut buffer = String::with_capacity(1024);
-    let label_max_len = padding.then(|| fields.iter().map(|f| f.label().len()).max().unwrap());
-    let tail = match fields.split_last() {
-        Some((tail, head)) => {
-            for field in head {
-                field.write(addr, &mut buffer, label_max_len).unwrap();
-                buffer.push('\n');
+    let mut widths = (0..columns.len())
+        .map(|i| column_width(i, |cell| cell.chars().count()))
+        .collect::<Vec<_>>();
+    // Cells are wrapped between words, which are never cut.
+    let min_widths = (0..columns.len())
+        .map(|i| {
+            column_width(i, |cell| {
+                cell.split(' ')
+                    .map(|word| word.chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+        })
+        .collect::<Vec<_>>();
+    // Only shrink the widest columns when displaying to a terminal, so piped
+    // values are never wrapped.
+    if let Some((Width(terminal_width), _)) = terminal_size::terminal_size() {
+        let separators = COLUMN_SEPARATOR.len() * widths.len().saturating_sub(1);
+        while widths.iter().sum::<usize>() + separators > terminal_width as usize {
+            let Some(widest) = (0..widths.len())
+                .filter(|&i| widths[i] > min_widths[i])
+                .max_by_key(|&i| widths[i])
+            else {
+                break;
+            };
+            widths[widest] -= 1;
+        }
+    }
+
+    for row in rows {
+        let cells = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, &width)| wrap(cell, width))
+            .collect::<Vec<_>>();
+        let height = cells.iter().map(Vec::len).max().unwrap_or(0);
+        for index in 0..height {
+            let mut line = String::with_capacity(256);
+            for (i, (cell, width)) in cells.iter().zip(&widths).enumerate() {
+                if i > 0 {
+                    line.push_str(COLUMN_SEPARATOR);
+                }
+                let text = cell.get(index).map_or("", String::as_str);
+                write!(line, "{text:width$}").unwrap();
             }
-            tail
+            // No trailing spaces.
+            println!("{}", line.trim_end());
         }
-        None => &fields[0],
-    };
-    tail.write(addr, &mut buffer, label_max_len).unwrap();
+    }
+}
+
+/// Split a cell into lines of at most the given width between words.
+fn wrap(cell: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![String::new()];
+    for word in cell.split(' ') {
+        let line = lines.last_mut().unwrap();
+        if line.is_empty() {
+            line.push_str(word);
+        } else if line.chars().count() + 1 + word.chars().count() <= width {
+            line.push(' ');
+            line.push_str(word);
+        } else {
+            lines.push(word.to_owned());
+        }
+    }
+    lines
+}
+
+pub fn list_fields() {
+    for field in Field::iter() {
+        println!("{}", <&str>::from(field));
+    }
+}
+
+fn process(input: AddrOrNet, fields: &[Field], padding: bool) -> String {
+    let values = fields
+        .iter()
+        .filter_map(|field| Some((field.label(), field.text_value(input)?)))
+        .collect::<Vec<_>>();
+    let label_max_len = values.iter().map(|(l, _)| l.len()).max().unwrap_or(0);
+
+    let mut buffer = String::with_capacity(1024);
+    for (label, value) in values {
+        if !buffer.is_empty() {
+            buffer.push('\n');
+        }
+        let padding = if padding {
+            label_max_len - label.len()
+        } else {
+            0
+        };
+        write!(buffer, "{label}:{:1$}{value}", "", padding + 1).unwrap();
+    }
     buffer
 }
 
-#[derive(Copy, Clone)]
-enum Field {
+/// Fields to display for each kind of input.
+struct FieldSelection {
+    ipv4: Vec<Field>,
+    ipv6: Vec<Field>,
+    range: Vec<Field>,
+}
+
+impl FieldSelection {
+    /// Fields specified on the command line take precedence over the
+    /// configuration ones, which take precedence over the defaults (all fields
+    /// for structured outputs).
+    fn new(fields: Vec<Field>, configuration: Option<Config>, structured: bool) -> Self {
+        if !fields.is_empty() || structured {
+            let fields = if fields.is_empty() {
+                Field::iter().collect::<Vec<_>>()
+            } else {
+                fields
+            };
+            return Self {
+                ipv4: fields.clone(),
+                ipv6: fields.clone(),
+                range: fields,
+            };
+        }
+        let configuration = configuration.and_then(|c| c.info).unwrap_or_default();
+        Self {
+            ipv4: configuration
+                .ipv4_fields
+                .unwrap_or_else(|| DEFAULT_IPV4_FIELDS.to_vec()),
+            ipv6: configuration
+                .ipv6_fields
+                .unwrap_or_else(|| DEFAULT_IPV6_FIELDS.to_vec()),
+            range: configuration
+                .range_fields
+                .unwrap_or_else(|| DEFAULT_RANGE_FIELDS.to_vec()),
+        }
+    }
+
+    /// Fields of every kind of input present, for layouts shared by all inputs.
+    fn columns(&self, inputs: &[AddrOrNet]) -> Vec<Field> {
+        let present = |kind: fn(&Option<IpNet>) -> bool| inputs.iter().any(|i| kind(&i.net()));
+        [
+            (&self.ipv4, present(|net| matches!(net, Some(IpNet::V4(_))))),
+            (&self.ipv6, present(|net| matches!(net, Some(IpNet::V6(_))))),
+            (&self.range, present(Option::is_none)),
+        ]
+        .into_iter()
+        .filter(|(_, present)| *present)
+        .flat_map(|(fields, _)| fields.iter().copied())
+        .unique()
+        .collect()
+    }
+
+    fn get(&self, input: AddrOrNet) -> &[Field] {
+        match input.net() {
+            Some(IpNet::V4(_)) => &self.ipv4,
+            Some(IpNet::V6(_)) => &self.ipv6,
+            None => &self.range,
+        }
+    }
+}
+
+#[derive(EnumString, IntoStaticStr, EnumIter, Eq, PartialEq, Hash, Copy, Clone, Debug)]
+#[strum(serialize_all = "snake_case")]
+pub enum Field {
     Address,
+    #[strum(serialize = "network")]
     NetworkAddress,
     HostsRange,
+    #[strum(serialize = "broadcast")]
     BroadcastAddress,
+    #[strum(serialize = "hosts")]
     HostsCount,
+    #[strum(serialize = "usable_hosts")]
     UsableHostsCount,
+    #[strum(serialize = "net_mask")]
     NetworkMaskAddress,
+    #[strum(serialize = "host_mask")]
     HostMaskAddress,
     Cidr,
+    #[strum(serialize = "full")]
     FullAddress,
     BinaryAddress,
+    #[strum(serialize = "binary_net_mask")]
     BinaryNetworkMaskAddress,
     Ipv6Mapping,
+    First,
+    Last,
+    Networks,
+    Scope,
+    #[strum(serialize = "registry_name")]
+    RegistryName,
+    Rfc,
+    GloballyReachable,
 }
 
 impl Field {
-    fn write(self, addr: IpNet, s: &mut String, label_max_len: Option<usize>) -> fmt::Result {
-        let label = self.label();
-        write!(
-            s,
-            "{label}:{:1$}",
-            "",
-            label_max_len.map(|m| m - label.len()).unwrap_or(0) + 1
-        )?;
-        match self {
-            Field::Address => write!(s, "{}", addr.addr()),
-            Field::NetworkAddress => write!(s, "{}", addr.network()),
-            Field::HostsRange => write!(
-                s,
+    /// Value of the field for the input, if applicable.
+    pub fn value(self, input: AddrOrNet) -> Option<Value> {
+        let range = input.range();
+        let Some(addr) = input.net() else {
+            return match self {
+                Field::First => Some(Value::string(range.start())),
+                Field::Last => Some(Value::string(range.end())),
+                Field::HostsCount => Some(Value::Count(range.size())),
+                Field::Networks => Some(Value::List(range.nets().map(|n| n.to_string()).collect())),
+                Field::Scope | Field::RegistryName | Field::Rfc | Field::GloballyReachable => {
+                    self.registry_value(input)
+                }
+                _ => None,
+            };
+        };
+        Some(match self {
+            Field::Address => Value::string(addr.addr()),
+            Field::NetworkAddress => Value::string(addr.network()),
+            Field::HostsRange => Value::String(format!(
                 "{} - {}",
                 addr.hosts().next().unwrap(),
                 addr.hosts().next_back().unwrap()
-            ),
-            Field::BroadcastAddress => write!(s, "{}", addr.broadcast()),
-            Field::HostsCount => write!(
-                s,
-                "{}",
-                2u128.pow((addr.max_prefix_len() - addr.prefix_len()) as u32)
-            ),
-            Field::UsableHostsCount => write!(s, "{}", addr.hosts().count()),
-            Field::NetworkMaskAddress => write!(s, "{}", addr.netmask()),
-            Field::HostMaskAddress => write!(s, "{}", addr.hostmask()),
-            Field::Cidr => write!(s, "{}", addr.prefix_len()),
-            Field::FullAddress => write!(s, "{}", addr),
-            Field::BinaryAddress => write!(s, "{}", to_binary(addr.addr())),
-            Field::BinaryNetworkMaskAddress => {
-                write!(s, "{}", to_binary(addr.netmask()))
-            }
+            )),
+            Field::BroadcastAddress => Value::string(addr.broadcast()),
+            Field::HostsCount => Value::Count(range.size()),
+            Field::UsableHostsCount => Value::Count(AddrCount::usable_hosts(&addr)),
+            Field::NetworkMaskAddress => Value::string(addr.netmask()),
+            Field::HostMaskAddress => Value::string(addr.hostmask()),
+            Field::Cidr => Value::Number(addr.prefix_len() as u128),
+            Field::FullAddress => Value::string(addr),
+            Field::BinaryAddress => Value::String(to_binary(addr.addr())),
+            Field::BinaryNetworkMaskAddress => Value::String(to_binary(addr.netmask())),
             Field::Ipv6Mapping => match addr {
-                IpNet::V4(addr) => write!(s, "{}", addr.addr().to_ipv6_compatible()),
-                IpNet::V6(_) => Field::Address.write(addr, s, label_max_len),
+                IpNet::V4(addr) => Value::string(addr.addr().to_ipv6_compatible()),
+                IpNet::V6(addr) => Value::string(addr.addr()),
             },
+            Field::First => Value::string(range.start()),
+            Field::Last => Value::string(range.end()),
+            Field::Networks => Value::List(vec![addr.trunc().to_string()]),
+            Field::Scope | Field::RegistryName | Field::Rfc | Field::GloballyReachable => {
+                return self.registry_value(input);
+            }
+        })
+    }
+
+    /// Value of the special-purpose registry fields, for any kind of input.
+    fn registry_value(self, input: AddrOrNet) -> Option<Value> {
+        let block = special_purpose::classify(input)?;
+        match self {
+            Field::Scope => Some(Value::string(<&str>::from(block.scope))),
+            Field::RegistryName => Some(Value::string(block.name)),
+            Field::Rfc => Some(Value::string(block.rfc)),
+            Field::GloballyReachable => block.globally_reachable.map(Value::Bool),
+            _ => None,
         }
     }
 
+    /// Human readable value of the field for the input, if applicable.
+    fn text_value(self, input: AddrOrNet) -> Option<String> {
+        Some(match self.value(input)? {
+            Value::Count(count) => format_count(count),
+            Value::List(values) => values.join(", "),
+            value => value.to_string(),
+        })
+    }
+
     fn label(self) -> &'static str {
         match self {
             Field::Address => "address",
@@ -152,10 +419,34 @@ impl Field {
             Field::BinaryAddress => "binary address",
             Field::BinaryNetworkMaskAddress => "binary net mask",
             Field::Ipv6Mapping => "IPv6 mapping",
+            Field::First => "first",
+            Field::Last => "last",
+            Field::Networks => "networks",
+            Field::Scope => "scope",
+            Field::RegistryName => "registry",
+            Field::Rfc => "RFC",
+            Field::GloballyReachable => "globally reachable",
         }
     }
 }
 
+impl<'de> Deserialize<'de> for Field {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        FromStr::from_str(&String::deserialize(deserializer)?).map_err(de::Error::custom)
+    }
+}
+
+fn format_count(count: AddrCount) -> String {
+    // Powers of two are more readable than exact values for large networks.
+    match count.log2() {
+        Some(exponent) if exponent >= 32 => format!("{count} (2^{exponent})"),
+        _ => count.to_string(),
+    }
+}
+
 fn to_binary(addr: IpAddr) -> String {
     match addr {
         IpAddr::V4(_) => addr
@@ -176,3 +467,61 @@ fn to_binary(addr: IpAddr) -> String {
             .join(":"),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use strum::IntoEnumIterator;
+
+    use super::{
+        DEFAULT_IPV4_FIELDS, DEFAULT_IPV6_FIELDS, DEFAULT_RANGE_FIELDS, Field, FieldSelection, wrap,
+    };
+    use crate::{addr_or_net::AddrOrNet, config::Config};
+
+    #[test]
+    fn field_selection() {
+        let configuration = || {
+            toml::from_str::<Config>(
+                r#"
+                [info]
+                ipv4_fields = ["network", "cidr"]
+                "#,
+            )
+            .unwrap()
+        };
+        let [ipv4, ipv6, range] = ["10.0.0.0/8", "2001:db8::/32", "10.0.0.1-10.0.0.9"]
+            .map(|input| input.parse::<AddrOrNet>().unwrap());
+
+        // Command line fields first, for every kind of input.
+        let selection = FieldSelection::new(vec![Field::Cidr], Some(configuration()), true);
+        assert_eq!(selection.get(ipv4), [Field::Cidr]);
+        assert_eq!(selection.get(ipv6), [Field::Cidr]);
+        assert_eq!(selection.get(range), [Field::Cidr]);
+
+        // Then the configuration ones, defaults filling the kinds it leaves out.
+        let selection = FieldSelection::new(Vec::new(), Some(configuration()), false);
+        assert_eq!(selection.get(ipv4), [Field::NetworkAddress, Field::Cidr]);
+        assert_eq!(selection.get(ipv6), DEFAULT_IPV6_FIELDS);
+        assert_eq!(selection.get(range), DEFAULT_RANGE_FIELDS);
+
+        let selection = FieldSelection::new(Vec::new(), None, false);
+        assert_eq!(selection.get(ipv4), DEFAULT_IPV4_FIELDS);
+
+        // Every field for structured outputs.
+        let selection = FieldSelection::new(Vec::new(), Some(configuration()), true);
+        assert_eq!(selection.get(ipv4), Field::iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn wrap_between_words() {
+        assert_eq!(
+            wrap("10.0.0.1 - 10.255.255.254", 13),
+            ["10.0.0.1 -", "10.255.255.254"]
+        );
+        assert_eq!(
+            wrap("10.1.2.3/32, 10.1.2.4/30", 30),
+            ["10.1.2.3/32, 10.1.2.4/30"]
+        );
+        assert_eq!(wrap("2001:db8::/64", 4), ["2001:db8::/64"]);
+        assert_eq!(wrap("", 8), [""]);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::{
+    addr_or_net::AddrOrNet,
+    config::Config,
+    group,
+    input::Input,
+    ip_set::IpSet,
+    output::{Printer, Value},
+    source::Source,
+};
+
+pub fn process(
+    sources: Vec<Source>,
+    others: Vec<Source>,
+    other_groups: Vec<String>,
+    entries: bool,
+    mut configuration: Option<Config>,
+    printer: &mut Printer,
+) -> Result<(), anyhow::Error> {
+    let inputs = Input::<AddrOrNet>::Lazy(sources)
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut others = Input::<AddrOrNet>::Lazy(others)
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()?;
+    for name in other_groups {
+        others.extend(
+            group::group_entries(&name, configuration.as_mut())?
+                .iter()
+                .copied(),
+        );
+    }
+
+    let inputs_set = inputs.iter().map(|i| i.range()).collect::<IpSet>();
+    let others_set = others.iter().map(|o| o.range()).collect::<IpSet>();
+    if entries {
+        for input in inputs.iter().filter(|i| others_set.overlaps(&i.range())) {
+            printer.print(*input, format_args!("< {input}"), || {
+                vec![
+                    ("side", Value::string("input")),
+                    ("entry", Value::string(input)),
+                ]
+            })?;
+        }
+        for other in others.iter().filter(|o| inputs_set.overlaps(&o.range())) {
+            printer.print(*other, format_args!("> {other}"), || {
+                vec![
+                    ("side", Value::string("other")),
+                    ("entry", Value::string(other)),
+                ]
+            })?;
+        }
+    } else {
+        for net in inputs_set.intersection(&others_set).nets() {
+            printer.print(net, net, || vec![("network", Value::string(net))])?;
+        }
+    }
+
+    Ok(())
+}
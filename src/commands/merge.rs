@@ -1,77 +1,197 @@
-use ipnet::IpNet;
+use std::{cmp::Reverse, collections::BinaryHeap};
 
-use crate::{auto_net::AutoNet, input::Input, source::Source};
+use anyhow::bail;
+use ipnet::IpNet;
+use itertools::Itertools;
 
-pub fn process(sources: Vec<Source>, sort: bool, unique: bool) -> Result<(), anyhow::Error> {
-    let mut input = Input::<AutoNet>::Lazy(sources);
-    if unique {
-        input.unique()?;
-    }
-    if sort {
-        input.sort()?;
-    }
+use crate::{
+    ip_range::{IpRange, to_bits},
+    ip_set::IpSet,
+    output::{Printer, Value},
+    source::Source,
+};
 
-    for net in merge(
-        &input
-            .into_iter()
-            .map(|entry| entry.map(|auto_net| auto_net.0.trunc()))
-            .collect::<Result<Vec<_>, _>>()?,
-    ) {
-        println!("{net}");
+pub fn process(
+    sources: Vec<Source>,
+    budget: Option<Budget>,
+    printer: &mut Printer,
+) -> Result<(), anyhow::Error> {
+    // Merged networks are always sorted and unique.
+    let nets = IpSet::load(sources)?.nets().collect::<Vec<_>>();
+    match budget {
+        Some(budget) => {
+            if let Budget::Prefixes(max) = budget {
+                // IP versions are never merged together.
+                let versions = nets.iter().map(|net| net.addr().is_ipv4()).unique().count();
+                if versions > max {
+                    bail!("IPv4 and IPv6 can't be summarized into less than {versions} networks");
+                }
+            }
+            let (nets, extra) = summarize(nets, budget);
+            for net in nets {
+                printer.print(net, net, || vec![("network", Value::string(net))])?;
+            }
+            // On stderr, so that it never mixes with the results.
+            eprintln!("{extra} extra address(es) covered");
+        }
+        None => {
+            for net in nets {
+                printer.print(net, net, || vec![("network", Value::string(net))])?;
+            }
+        }
     }
 
     Ok(())
 }
 
-fn merge(nets: &[IpNet]) -> Vec<IpNet> {
-    if nets.is_empty() {
-        return Vec::new();
+#[derive(Copy, Clone, Debug)]
+pub enum Budget {
+    /// Maximum number of networks.
+    Prefixes(usize),
+    /// Maximum number of addresses covered in addition to the inputs.
+    Extra(u128),
+}
+
+/// Greedily replace neighbouring networks by their common supernet, cheapest
+/// first (in additional addresses covered), until the budget is met. Networks
+/// must be sorted, disjoint and truncated.
+fn summarize(nets: Vec<IpNet>, budget: Budget) -> (Vec<IpNet>, u128) {
+    let len = nets.len();
+    let mut nets = nets.into_iter().map(Some).collect::<Vec<_>>();
+    let mut prev = (0..len).map(|i| i.checked_sub(1)).collect::<Vec<_>>();
+    let mut next = (0..len)
+        .map(|i| (i + 1 < len).then_some(i + 1))
+        .collect::<Vec<_>>();
+
+    let mut candidates = BinaryHeap::new();
+    for (left, right) in (0..len).tuple_windows() {
+        if let Some(cost) = merge_cost(&nets, &prev, &next, left, right) {
+            candidates.push(Reverse((cost, left, right)));
+        }
     }
 
-    let mut nets = nets.to_owned();
-    loop {
-        let mut merged = Vec::with_capacity(nets.len());
-        let mut current = nets[0];
-        for &net in &nets[1..] {
-            if current.contains(&net) {
-                continue;
-            }
-            if net.contains(&current) {
-                current = net;
+    let (mut count, mut extra) = (len, 0u128);
+    while let Some(Reverse((cost, left, right))) = candidates.pop() {
+        if nets[left].is_none() || next[left] != Some(right) {
+            continue;
+        }
+        // Neighbours might have changed since the candidate was pushed.
+        match merge_cost(&nets, &prev, &next, left, right) {
+            Some(current) if current == cost => {}
+            Some(current) => {
+                candidates.push(Reverse((current, left, right)));
                 continue;
             }
-            if current.supernet().expect("unexpected top net")
-                == net.supernet().expect("unexpected top net")
+            None => continue,
+        }
+        match budget {
+            Budget::Prefixes(max) if count <= max => break,
+            Budget::Extra(max) if extra.saturating_add(cost) > max => break,
+            _ => {}
+        }
+
+        let supernet = common_supernet(nets[left].unwrap(), nets[right].unwrap());
+        nets[left] = Some(supernet);
+        for direction in [&mut next, &mut prev] {
+            while let Some(neighbour) =
+                direction[left].filter(|&n| nets[n].is_some_and(|n| supernet.contains(&n)))
             {
-                current = current.supernet().expect("unexpected top net");
-                continue;
+                nets[neighbour] = None;
+                direction[left] = direction[neighbour];
+                count -= 1;
             }
+        }
+        if let Some(n) = next[left] {
+            prev[n] = Some(left);
+        }
+        if let Some(p) = prev[left] {
+            next[p] = Some(left);
+        }
+        extra = extra.saturating_add(cost);
 
-            merged.push(current);
-            current = net;
+        for (l, r) in [(prev[left], Some(left)), (Some(left), next[left])] {
+            if let (Some(l), Some(r)) = (l, r)
+                && let Some(cost) = merge_cost(&nets, &prev, &next, l, r)
+            {
+                candidates.push(Reverse((cost, l, r)));
+            }
         }
-        merged.push(current);
+    }
+
+    // Summarization might have created aligned siblings, merging them is free.
+    let nets = nets
+        .into_iter()
+        .flatten()
+        .map(IpRange::from)
+        .collect::<IpSet>();
+    (nets.nets().collect(), extra)
+}
+
+/// Number of addresses covered by the common supernet of two neighbouring
+/// networks and by none of the existing ones.
+fn merge_cost(
+    nets: &[Option<IpNet>],
+    prev: &[Option<usize>],
+    next: &[Option<usize>],
+    left: usize,
+    right: usize,
+) -> Option<u128> {
+    let (left_net, right_net) = (nets[left]?, nets[right]?);
+    if left_net.addr().is_ipv4() != right_net.addr().is_ipv4() {
+        return None;
+    }
+    let supernet = common_supernet(left_net, right_net);
 
-        if merged.len() == nets.len() {
-            return merged;
+    // Sizes minus one, as a full IPv6 address space doesn't fit in an u128.
+    let size = |net: IpNet| to_bits(net.hostmask());
+    let mut covered = size(left_net);
+    for direction in [next, prev] {
+        let mut current = direction[left];
+        while let Some(net) = current
+            .and_then(|c| nets[c])
+            .filter(|n| supernet.contains(n))
+        {
+            covered += size(net) + 1;
+            current = direction[current.unwrap()];
         }
-        nets = merged;
     }
+    Some(size(supernet) - covered)
+}
+
+fn common_supernet(a: IpNet, b: IpNet) -> IpNet {
+    let common_bits =
+        (to_bits(a.addr()) ^ to_bits(b.addr())).leading_zeros() - (128 - a.max_prefix_len() as u32);
+    let prefix_len = (common_bits as u8).min(a.prefix_len()).min(b.prefix_len());
+    IpNet::new(a.addr(), prefix_len)
+        .expect("unexpected invalid prefix length")
+        .trunc()
 }
 
 #[cfg(test)]
 mod tests {
+    use ipnet::IpNet;
+
+    use crate::{ip_range::IpRange, ip_set::IpSet};
+
+    fn merge(nets: &[IpNet]) -> Vec<IpNet> {
+        nets.iter()
+            .map(|&net| IpRange::from(net))
+            .collect::<IpSet>()
+            .nets()
+            .collect()
+    }
+
     #[test]
     fn contains() {
         assert_eq!(
-            super::merge(&[
+            merge(&[
                 "10.0.0.0/31".parse().unwrap(),
                 "10.0.0.1/32".parse().unwrap(),
             ]),
             ["10.0.0.0/31".parse().unwrap(),]
         );
         assert_eq!(
-            super::merge(&[
+            merge(&[
                 "10.0.0.0/24".parse().unwrap(),
                 "10.0.0.1/32".parse().unwrap(),
             ]),
@@ -79,7 +199,7 @@ mod tests {
         );
         // Other way.
         assert_eq!(
-            super::merge(&[
+            merge(&[
                 "10.0.0.1/32".parse().unwrap(),
                 "10.0.0.0/24".parse().unwrap(),
             ]),
@@ -90,7 +210,7 @@ mod tests {
     #[test]
     fn supernet() {
         assert_eq!(
-            super::merge(&[
+            merge(&[
                 "10.0.0.0/32".parse().unwrap(),
                 "10.0.0.1/32".parse().unwrap(),
             ]),
@@ -98,7 +218,7 @@ mod tests {
         );
         // Can't be merged.
         assert_eq!(
-            super::merge(&[
+            merge(&[
                 "10.0.0.1/32".parse().unwrap(),
                 "10.0.0.2/32".parse().unwrap(),
             ]),
@@ -109,11 +229,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn summarize() {
+        use super::Budget;
+
+        let nets = [
+            "10.0.0.0/24".parse().unwrap(),
+            "10.0.2.0/24".parse().unwrap(),
+            "10.0.4.0/22".parse().unwrap(),
+            "10.1.0.0/24".parse().unwrap(),
+            "192.168.0.0/24".parse().unwrap(),
+        ];
+        assert_eq!(
+            super::summarize(nets.to_vec(), Budget::Prefixes(3)),
+            (
+                vec![
+                    "10.0.0.0/21".parse().unwrap(),
+                    "10.1.0.0/24".parse().unwrap(),
+                    "192.168.0.0/24".parse().unwrap(),
+                ],
+                512
+            )
+        );
+        assert_eq!(
+            super::summarize(nets.to_vec(), Budget::Extra(512)),
+            super::summarize(nets.to_vec(), Budget::Prefixes(3)),
+        );
+        assert_eq!(
+            super::summarize(nets.to_vec(), Budget::Extra(511)),
+            (nets.to_vec(), 0)
+        );
+        assert_eq!(
+            super::summarize(nets.to_vec(), Budget::Prefixes(10)),
+            (nets.to_vec(), 0)
+        );
+        // IP versions are never mixed.
+        assert_eq!(
+            super::summarize(
+                vec!["0.0.0.0/1".parse().unwrap(), "::/1".parse().unwrap()],
+                Budget::Prefixes(1)
+            ),
+            (
+                vec!["0.0.0.0/1".parse().unwrap(), "::/1".parse().unwrap()],
+                0
+            )
+        );
+    }
+
     #[test]
     fn list() {
         // 4x/32 into 2x/31 into 1x/30.
         assert_eq!(
-            super::merge(&[
+            merge(&[
                 "10.0.0.0/32".parse().unwrap(),
                 "10.0.0.1/32".parse().unwrap(),
                 "10.0.0.2/32".parse().unwrap(),
@@ -122,23 +289,22 @@ mod tests {
             ["10.0.0.0/30".parse().unwrap()]
         );
 
-        // Only consecutive nets are merged.
+        // Order doesn't matter.
         assert_eq!(
-            super::merge(&[
+            merge(&[
                 "10.0.0.0/32".parse().unwrap(),
                 "10.0.1.0/32".parse().unwrap(),
                 "10.0.0.1/32".parse().unwrap(),
             ]),
             [
-                "10.0.0.0/32".parse().unwrap(),
+                "10.0.0.0/31".parse().unwrap(),
                 "10.0.1.0/32".parse().unwrap(),
-                "10.0.0.1/32".parse().unwrap(),
             ]
         );
 
         // Complex list.
         assert_eq!(
-            super::merge(&[
+            merge(&[
                 "10.0.0.0/25".parse().unwrap(),
                 "10.0.0.128/25".parse().unwrap(),
                 "10.0.1.0/24".parse().unwrap(),
@@ -173,4 +339,17 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn top() {
+        assert_eq!(
+            merge(&[
+                "::/1".parse().unwrap(),
+                "0.0.0.0/0".parse().unwrap(),
+                "10.0.0.0/8".parse().unwrap(),
+                "8000::/1".parse().unwrap(),
+            ]),
+            ["0.0.0.0/0".parse().unwrap(), "::/0".parse().unwrap()]
+        );
+    }
 }
@@ -1,8 +1,17 @@
 pub mod cat;
+pub mod classify;
+pub mod complement;
+pub mod contains;
+pub mod diff;
+pub mod exclude;
 pub mod filter;
 pub mod group;
+pub mod groups;
 pub mod hosts;
 pub mod info;
+pub mod intersect;
 pub mod merge;
 pub mod net;
+pub mod overlap;
+pub mod range;
 pub mod subnet;
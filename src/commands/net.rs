@@ -3,7 +3,11 @@ use std::net::IpAddr;
 use ipnet::IpNet;
 use itertools::Itertools;
 
-use crate::{input::Input, source::Source};
+use crate::{
+    input::Input,
+    output::{Printer, Value},
+    source::Source,
+};
 
 pub fn process_batch(
     sources: Vec<Source>,
@@ -11,32 +15,27 @@ pub fn process_batch(
     cidr: bool,
     sort: bool,
     unique: bool,
+    printer: &mut Printer,
 ) -> Result<(), anyhow::Error> {
     let input = Input::<IpAddr>::Lazy(sources);
     if sort || unique {
         let mut nets = input
             .into_iter()
-            .map(|addr| process_single(addr?, prefix_len))
+            .map(|addr| addr.and_then(|addr| Ok((addr, process_single(addr, prefix_len)?))))
             .collect::<Result<Vec<_>, _>>()?;
         if sort {
-            nets.sort();
+            nets.sort_by_key(|(_, net)| *net);
         }
         if unique {
-            nets = nets.into_iter().unique().collect();
+            nets = nets.into_iter().unique_by(|(_, net)| *net).collect();
         }
-        if cidr {
-            println!("{}", nets.iter().join("\n"));
-        } else {
-            println!("{}", nets.iter().map(IpNet::addr).join("\n"));
+        for (addr, net) in nets {
+            print(printer, addr, net, cidr)?;
         }
     } else {
         for addr in input {
-            let net = process_single(addr?, prefix_len)?;
-            if cidr {
-                println!("{}", net);
-            } else {
-                println!("{}", net.addr());
-            }
+            let addr = addr?;
+            print(printer, addr, process_single(addr, prefix_len)?, cidr)?;
         }
     }
 
@@ -46,3 +45,17 @@ pub fn process_batch(
 fn process_single(addr: IpAddr, prefix_len: u8) -> Result<IpNet, anyhow::Error> {
     Ok(IpNet::new(addr, prefix_len)?.trunc())
 }
+
+fn print(printer: &mut Printer, addr: IpAddr, net: IpNet, cidr: bool) -> Result<(), anyhow::Error> {
+    let text = if cidr {
+        net.to_string()
+    } else {
+        net.addr().to_string()
+    };
+    printer.print(net, &text, || {
+        vec![
+            ("input", Value::string(addr)),
+            ("network", Value::string(&text)),
+        ]
+    })
+}
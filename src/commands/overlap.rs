@@ -0,0 +1,137 @@
+use std::cmp::Reverse;
+
+use crate::{
+    addr_or_net::AddrOrNet,
+    input,
+    ip_range::IpRange,
+    output::{Printer, Value},
+    source::Source,
+};
+
+/// Returns whether any entries overlap.
+pub fn process(sources: Vec<Source>, printer: &mut Printer) -> Result<bool, anyhow::Error> {
+    let entries = input::located::<AddrOrNet>(sources).collect::<Result<Vec<_>, _>>()?;
+
+    let conflicts = conflicts(
+        &entries
+            .iter()
+            .map(|(_, entry)| entry.range())
+            .collect::<Vec<_>>(),
+    );
+
+    for &(a, b) in &conflicts {
+        let ((location_a, entry_a), (location_b, entry_b)) = (&entries[a], &entries[b]);
+        let relation = Relation::new(*entry_a, *entry_b);
+        printer.print(
+            *entry_a,
+            format_args!(
+                "{location_a}: {entry_a} {} {entry_b} ({location_b})",
+                relation.label()
+            ),
+            || {
+                vec![
+                    ("location", Value::string(location_a)),
+                    ("entry", Value::string(entry_a)),
+                    ("relation", Value::string(relation.name())),
+                    ("other_location", Value::string(location_b)),
+                    ("other_entry", Value::string(entry_b)),
+                ]
+            },
+        )?;
+    }
+
+    Ok(!conflicts.is_empty())
+}
+
+/// Pairs of overlapping ranges (by index, lowest first), in index order.
+fn conflicts(ranges: &[IpRange]) -> Vec<(usize, usize)> {
+    // Sort by start address then widest first, so containing entries come before
+    // the ones they contain.
+    let mut order = (0..ranges.len()).collect::<Vec<_>>();
+    order.sort_by_key(|&i| (ranges[i].start(), Reverse(ranges[i].end()), i));
+
+    let mut conflicts = Vec::new();
+    let mut active = Vec::<usize>::new();
+    for i in order {
+        active.retain(|&a| ranges[a].end() >= ranges[i].start());
+        for &a in &active {
+            conflicts.push((a.min(i), a.max(i)));
+        }
+        active.push(i);
+    }
+    conflicts.sort_unstable();
+    conflicts
+}
+
+enum Relation {
+    Duplicate,
+    Contains,
+    ContainedBy,
+    Overlaps,
+}
+
+impl Relation {
+    fn new(a: AddrOrNet, b: AddrOrNet) -> Self {
+        let (a, b) = (a.range(), b.range());
+        if a == b {
+            Relation::Duplicate
+        } else if a.contains(&b) {
+            Relation::Contains
+        } else if b.contains(&a) {
+            Relation::ContainedBy
+        } else {
+            Relation::Overlaps
+        }
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Relation::Duplicate => "duplicate",
+            Relation::Contains => "contains",
+            Relation::ContainedBy => "contained_by",
+            Relation::Overlaps => "overlaps",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            Relation::Duplicate => "duplicates",
+            Relation::Contains => "contains",
+            Relation::ContainedBy => "is contained by",
+            Relation::Overlaps => "overlaps",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Relation, conflicts};
+    use crate::addr_or_net::AddrOrNet;
+
+    #[test]
+    fn conflicting_entries() {
+        let entries = [
+            "10.0.0.0/24",
+            "10.0.0.0/25",
+            "10.0.1.0/24",
+            "10.0.0.0-10.0.0.255",
+            "10.0.1.128-10.0.2.127",
+            "10.0.2.128/25",
+            "::a00:0/120",
+        ]
+        .map(|entry| entry.parse::<AddrOrNet>().unwrap());
+        let conflicts = conflicts(&entries.map(AddrOrNet::range))
+            .into_iter()
+            .map(|(a, b)| (a, b, Relation::new(entries[a], entries[b]).name()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            [
+                (0, 1, "contains"),
+                (0, 3, "duplicate"),
+                (1, 3, "contained_by"),
+                (2, 4, "overlaps"),
+            ]
+        );
+    }
+}
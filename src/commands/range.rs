@@ -0,0 +1,151 @@
+use ipnet::IpNet;
+use itertools::Itertools;
+
+use crate::{
+    addr_or_net::AddrOrNet,
+    input::Input,
+    ip_set::IpSet,
+    output::{Printer, Value},
+    source::Source,
+};
+
+pub fn process_batch(
+    sources: Vec<Source>,
+    to_range: bool,
+    sort: bool,
+    unique: bool,
+    printer: &mut Printer,
+) -> Result<(), anyhow::Error> {
+    if to_range {
+        // Ranges are collapsed into a sorted set, already sorted and without
+        // duplicates.
+        for range in IpSet::load(sources)?.ranges() {
+            printer.print(*range, range, || {
+                vec![
+                    ("range", Value::string(range)),
+                    ("first", Value::string(range.start())),
+                    ("last", Value::string(range.end())),
+                ]
+            })?;
+        }
+        return Ok(());
+    }
+
+    let input = Input::<AddrOrNet>::Lazy(sources);
+    if sort || unique {
+        for (value, net) in networks(input, sort, unique)? {
+            print(printer, value, net)?;
+        }
+    } else {
+        for value in input {
+            let value = value?;
+            for net in value.nets() {
+                print(printer, value, net)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print(printer: &mut Printer, value: AddrOrNet, net: IpNet) -> Result<(), anyhow::Error> {
+    printer.print(net, net, || {
+        vec![
+            ("input", Value::string(value)),
+            ("network", Value::string(net)),
+        ]
+    })
+}
+
+/// Networks of every input along with the input, sorted by network and/or
+/// without duplicate networks.
+fn networks(
+    input: Input<AddrOrNet>,
+    sort: bool,
+    unique: bool,
+) -> Result<Vec<(AddrOrNet, IpNet)>, anyhow::Error> {
+    let mut nets = input
+        .into_iter()
+        .map_ok(|value| value.nets().map(move |net| (value, net)))
+        .flatten_ok()
+        .collect::<Result<Vec<_>, _>>()?;
+    if sort {
+        nets.sort_by_key(|(_, net)| *net);
+    }
+    if unique {
+        nets = nets.into_iter().unique_by(|(_, net)| *net).collect();
+    }
+    Ok(nets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::networks;
+    use crate::{input::Input, ip_set::IpSet, source::Source};
+
+    fn sources(inputs: &[&str]) -> Vec<Source> {
+        inputs
+            .iter()
+            .map(|input| Source::Arg((*input).to_owned()))
+            .collect()
+    }
+
+    #[test]
+    fn to_cidr() {
+        let nets = |sort, unique| {
+            networks(
+                Input::Lazy(sources(&["10.0.0.5-10.0.0.8", "10.0.0.0/30", "10.0.0.6"])),
+                sort,
+                unique,
+            )
+            .unwrap()
+            .into_iter()
+            .map(|(value, net)| format!("{value} {net}"))
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            nets(false, false),
+            [
+                "10.0.0.5-10.0.0.8 10.0.0.5/32",
+                "10.0.0.5-10.0.0.8 10.0.0.6/31",
+                "10.0.0.5-10.0.0.8 10.0.0.8/32",
+                "10.0.0.0/30 10.0.0.0/30",
+                "10.0.0.6 10.0.0.6/32",
+            ]
+        );
+        assert_eq!(
+            nets(true, true),
+            [
+                "10.0.0.0/30 10.0.0.0/30",
+                "10.0.0.5-10.0.0.8 10.0.0.5/32",
+                "10.0.0.5-10.0.0.8 10.0.0.6/31",
+                "10.0.0.6 10.0.0.6/32",
+                "10.0.0.5-10.0.0.8 10.0.0.8/32",
+            ]
+        );
+    }
+
+    #[test]
+    fn to_range() {
+        let ranges = IpSet::load(sources(&[
+            "10.0.0.128/25",
+            "10.0.0.0/25",
+            "10.0.1.5",
+            "2001:db8::/127",
+        ]))
+        .unwrap()
+        .ranges()
+        .iter()
+        .map(|range| range.to_string())
+        .collect::<Vec<_>>();
+        // Adjacent entries are collapsed, in address order.
+        assert_eq!(
+            ranges,
+            [
+                "10.0.0.0-10.0.0.255",
+                "10.0.1.5-10.0.1.5",
+                "2001:db8::-2001:db8::1",
+            ]
+        );
+    }
+}
@@ -2,7 +2,11 @@ use anyhow::{Context, bail};
 use ipnet::{IpNet, IpSubnets};
 use itertools::Itertools;
 
-use crate::{input::Input, source::Source};
+use crate::{
+    input::Input,
+    output::{Printer, Value},
+    source::Source,
+};
 
 pub fn process_batch(
     sources: Vec<Source>,
@@ -10,33 +14,32 @@ pub fn process_batch(
     cidr: bool,
     sort: bool,
     unique: bool,
+    printer: &mut Printer,
 ) -> Result<(), anyhow::Error> {
     let input = Input::<IpNet>::Lazy(sources);
     if sort || unique {
         let mut nets = input
             .into_iter()
-            .map(|net| process_single(net?, prefix_len))
+            .map(|net| {
+                let net = net?;
+                Ok::<_, anyhow::Error>(process_single(net, prefix_len)?.map(move |s| (net, s)))
+            })
             .flatten_ok()
             .collect::<Result<Vec<_>, _>>()?;
         if sort {
-            nets.sort();
+            nets.sort_by_key(|(_, subnet)| *subnet);
         }
         if unique {
-            nets = nets.into_iter().unique().collect();
+            nets = nets.into_iter().unique_by(|(_, subnet)| *subnet).collect();
         }
-        if cidr {
-            println!("{}", nets.iter().join("\n"));
-        } else {
-            println!("{}", nets.iter().map(IpNet::addr).join("\n"));
+        for (net, subnet) in nets {
+            print(printer, net, subnet, cidr)?;
         }
     } else {
         for net in input {
-            for subnet in process_single(net?, prefix_len)? {
-                if cidr {
-                    println!("{}", subnet);
-                } else {
-                    println!("{}", subnet.addr());
-                }
+            let net = net?;
+            for subnet in process_single(net, prefix_len)? {
+                print(printer, net, subnet, cidr)?;
             }
         }
     }
@@ -51,3 +54,22 @@ fn process_single(net: IpNet, prefix_len: u8) -> Result<IpSubnets, anyhow::Error
     net.subnets(prefix_len)
         .context("invalid subnet prefix length")
 }
+
+fn print(
+    printer: &mut Printer,
+    net: IpNet,
+    subnet: IpNet,
+    cidr: bool,
+) -> Result<(), anyhow::Error> {
+    let text = if cidr {
+        subnet.to_string()
+    } else {
+        subnet.addr().to_string()
+    };
+    printer.print(subnet, &text, || {
+        vec![
+            ("input", Value::string(net)),
+            ("subnet", Value::string(&text)),
+        ]
+    })
+}
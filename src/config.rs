@@ -4,11 +4,14 @@ use anyhow::{Context, Error as AnyError};
 use config_finder::ConfigDirs;
 use serde::Deserialize;
 
-use crate::auto_net::AutoNet;
+use crate::{addr_or_net::AddrOrNet, commands::info::Field, group::GroupIndex};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 pub struct Config {
     pub groups: Option<Vec<Group>>,
+    pub info: Option<InfoConfig>,
+    #[serde(skip)]
+    pub group_index: Option<GroupIndex>,
 }
 
 impl Config {
@@ -34,18 +37,57 @@ impl Config {
     }
 }
 
-#[derive(Deserialize, Debug)]
+/// Configuration only read when first needed, so that commands not using it
+/// aren't affected by a broken file.
+pub struct LazyConfig {
+    path: Option<PathBuf>,
+    loaded: Option<Option<Config>>,
+}
+
+impl LazyConfig {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        Self { path, loaded: None }
+    }
+
+    pub fn get_mut(&mut self) -> Result<Option<&mut Config>, AnyError> {
+        if self.loaded.is_none() {
+            self.loaded = Some(Config::load(self.path.clone())?);
+        }
+        Ok(self.loaded.as_mut().unwrap().as_mut())
+    }
+
+    pub fn into_inner(mut self) -> Result<Option<Config>, AnyError> {
+        self.get_mut()?;
+        Ok(self.loaded.unwrap())
+    }
+
+    /// Loaded configuration if `needed`, not read otherwise.
+    pub fn into_inner_if(self, needed: bool) -> Result<Option<Config>, AnyError> {
+        if needed { self.into_inner() } else { Ok(None) }
+    }
+}
+
+#[derive(Deserialize, Default, Clone, Debug)]
+pub struct InfoConfig {
+    pub ipv4_fields: Option<Vec<Field>>,
+    pub ipv6_fields: Option<Vec<Field>>,
+    pub range_fields: Option<Vec<Field>>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
 pub struct Group {
     pub name: String,
     #[serde(flatten)]
     pub source: GroupSource,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Clone, Debug)]
 #[serde(untagged)]
 pub enum GroupSource {
     Raw {
-        nets: Vec<AutoNet>,
+        /// Addresses, networks and ranges, as written.
+        #[serde(rename = "nets")]
+        entries: Vec<AddrOrNet>,
     },
     File {
         file: PathBuf,
@@ -57,18 +99,18 @@ pub enum GroupSource {
 }
 
 impl GroupSource {
-    pub fn load(&mut self) -> Result<&[AutoNet], AnyError> {
+    pub fn load(&mut self) -> Result<&[AddrOrNet], AnyError> {
         match self {
-            GroupSource::Raw { nets } => Ok(nets),
+            GroupSource::Raw { entries } => Ok(entries),
             GroupSource::File { file: path } => {
-                let nets = fs::read_to_string(path)?
+                let entries = fs::read_to_string(path)?
                     .trim()
                     .lines()
                     .filter(|l| !l.trim().is_empty())
-                    .map(str::parse)
+                    .map(|line| line.trim().parse())
                     .collect::<Result<Vec<_>, _>>()
                     .context("invalid group file content")?;
-                *self = Self::Raw { nets };
+                *self = Self::Raw { entries };
                 self.load()
             }
             GroupSource::Command { command, shell } => {
@@ -83,14 +125,14 @@ impl GroupSource {
                         .context("group command failure")?
                         .stdout,
                 )?;
-                let nets = output
+                let entries = output
                     .trim()
                     .lines()
                     .filter(|l| !l.trim().is_empty())
-                    .map(str::parse)
+                    .map(|line| line.trim().parse())
                     .collect::<Result<Vec<_>, _>>()
                     .context("invalid group command output")?;
-                *self = Self::Raw { nets };
+                *self = Self::Raw { entries };
                 self.load()
             }
         }
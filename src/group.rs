@@ -1,31 +1,195 @@
-use anyhow::bail;
+use std::{cmp::Reverse, collections::HashMap};
 
-use crate::{addr_or_net::AddrOrNet, config::Config};
+use anyhow::{Context, bail};
+use clap::ValueEnum;
+use either::Either;
+use ipnet::IpNet;
+use itertools::Itertools;
 
+use crate::{
+    addr_or_net::AddrOrNet,
+    config::{Config, Group},
+    prefix_trie::PrefixTrie,
+};
+
+/// Networks of every group, indexed by position of the group in the
+/// configuration.
+#[derive(Clone, Debug)]
+pub struct GroupIndex(PrefixTrie<usize>);
+
+impl GroupIndex {
+    fn build(groups: &mut [Group]) -> Result<Self, anyhow::Error> {
+        let mut trie = PrefixTrie::new();
+        for (position, group) in groups.iter_mut().enumerate() {
+            for net in group.source.load()?.iter().flat_map(|entry| entry.nets()) {
+                trie.insert(net, position);
+            }
+        }
+        Ok(Self(trie))
+    }
+}
+
+/// How to choose among the groups containing an input.
+#[derive(ValueEnum, Copy, Clone, Default, Debug)]
+pub enum GroupMatch {
+    /// First group in configuration order.
+    #[default]
+    First,
+    /// Group of the longest matching prefix (first in configuration order on
+    /// ties).
+    MostSpecific,
+    /// Every group, in configuration order.
+    All,
+}
+
+/// Groups with a network containing the whole input, in configuration order.
 pub fn matching_groups(
     input: AddrOrNet,
     configuration: Option<&mut Config>,
-) -> Result<impl Iterator<Item = Result<&str, anyhow::Error>>, anyhow::Error> {
-    let groups = match configuration {
+    group_match: GroupMatch,
+) -> Result<impl Iterator<Item = &str>, anyhow::Error> {
+    Ok(matching_group_nets(input, configuration, group_match)?.map(|(name, _)| name))
+}
+
+/// Same as [`matching_groups`], along with the most specific network of each
+/// group containing the input.
+pub fn matching_group_nets(
+    input: AddrOrNet,
+    configuration: Option<&mut Config>,
+    group_match: GroupMatch,
+) -> Result<impl Iterator<Item = (&str, IpNet)>, anyhow::Error> {
+    let (groups, index) = indexed_groups(configuration)?;
+    let range = input.range();
+    // Networks come from the least to the most specific: the last prefix length
+    // seen for a group is its longest.
+    let mut matches = HashMap::new();
+    for (prefix_len, &position) in index.0.containing(&range) {
+        matches.insert(position, prefix_len);
+    }
+    let matches = match group_match {
+        GroupMatch::First => Either::Left(
+            matches
+                .into_iter()
+                .min_by_key(|&(position, _)| position)
+                .into_iter(),
+        ),
+        GroupMatch::MostSpecific => Either::Left(
+            matches
+                .into_iter()
+                .max_by_key(|&(position, prefix_len)| (prefix_len, Reverse(position)))
+                .into_iter(),
+        ),
+        GroupMatch::All => Either::Right(matches.into_iter().sorted_unstable()),
+    };
+    Ok(matches.map(move |(position, prefix_len)| {
+        let net = IpNet::new(range.start(), prefix_len)
+            .expect("unexpected invalid prefix length")
+            .trunc();
+        (groups[position].name.as_str(), net)
+    }))
+}
+
+/// Load every group and build their index ahead of the first lookup.
+pub fn index_groups(configuration: Option<&mut Config>) -> Result<(), anyhow::Error> {
+    indexed_groups(configuration).map(|_| ())
+}
+
+fn indexed_groups(
+    configuration: Option<&mut Config>,
+) -> Result<(&[Group], &GroupIndex), anyhow::Error> {
+    let Some(configuration) = configuration else {
+        bail!("configuration required to filter based on groups");
+    };
+    let Some(groups) = &mut configuration.groups else {
+        bail!("no groups defined in configuration");
+    };
+    // Built once, on first lookup.
+    if configuration.group_index.is_none() {
+        configuration.group_index = Some(GroupIndex::build(groups)?);
+    }
+    Ok((groups, configuration.group_index.as_ref().unwrap()))
+}
+
+/// Addresses, networks and ranges of a group, as written.
+pub fn group_entries<'a>(
+    name: &str,
+    configuration: Option<&'a mut Config>,
+) -> Result<&'a [AddrOrNet], anyhow::Error> {
+    groups(configuration)?
+        .iter_mut()
+        .find(|group| group.name == name)
+        .with_context(|| format!("unknown group: {name}"))?
+        .source
+        .load()
+}
+
+/// Entries of every group by name (groups defined several times are merged).
+pub fn all_group_entries(
+    configuration: Option<&mut Config>,
+) -> Result<HashMap<String, Vec<AddrOrNet>>, anyhow::Error> {
+    let mut entries = HashMap::<String, Vec<AddrOrNet>>::new();
+    for group in groups(configuration)? {
+        entries
+            .entry(group.name.clone())
+            .or_default()
+            .extend_from_slice(group.source.load()?);
+    }
+    Ok(entries)
+}
+
+pub fn groups(configuration: Option<&mut Config>) -> Result<&mut Vec<Group>, anyhow::Error> {
+    match configuration {
         Some(configuration) => match &mut configuration.groups {
-            Some(groups) => groups,
+            Some(groups) => Ok(groups),
             None => bail!("no groups defined in configuration"),
         },
         None => bail!("configuration required to filter based on groups"),
-    };
+    }
+}
 
-    Ok(groups.iter_mut().flat_map(move |group| {
-        group
-            .source
-            .load()
-            .map(|nets| {
-                nets.iter()
-                    .any(|net| match input {
-                        AddrOrNet::IpAddr(addr) => net.0.contains(&addr),
-                        AddrOrNet::IpNet(sub_net) => net.0.contains(&sub_net),
-                    })
-                    .then_some(group.name.as_str())
-            })
-            .transpose()
-    }))
+#[cfg(test)]
+mod tests {
+    use super::{GroupMatch, matching_groups};
+    use crate::config::Config;
+
+    #[test]
+    fn group_match() {
+        let mut configuration = toml::from_str::<Config>(
+            r#"
+            [[groups]]
+            name = "corp"
+            nets = ["10.0.0.0/8"]
+            [[groups]]
+            name = "dc1"
+            nets = ["10.1.0.0/16", "10.1.2.0/24"]
+            [[groups]]
+            name = "dc1-mgmt"
+            nets = ["10.1.2.0/24"]
+            [[groups]]
+            name = "lab"
+            nets = ["10.1.0.0/16"]
+            "#,
+        )
+        .unwrap();
+        let mut groups = |input: &str, group_match| {
+            matching_groups(
+                input.parse().unwrap(),
+                Some(&mut configuration),
+                group_match,
+            )
+            .unwrap()
+            .collect::<Vec<_>>()
+            .join(",")
+        };
+        assert_eq!(groups("10.1.2.3", GroupMatch::First), "corp");
+        assert_eq!(groups("10.1.2.3", GroupMatch::All), "corp,dc1,dc1-mgmt,lab");
+        assert_eq!(groups("10.9.0.0/16", GroupMatch::All), "corp");
+        assert_eq!(groups("192.0.2.1", GroupMatch::All), "");
+        // Longest prefix, first in configuration order on ties.
+        assert_eq!(groups("10.1.2.3", GroupMatch::MostSpecific), "dc1");
+        assert_eq!(groups("10.1.3.0/24", GroupMatch::MostSpecific), "dc1");
+        assert_eq!(groups("10.200.0.1", GroupMatch::MostSpecific), "corp");
+        // A range spanning several networks only matches the ones containing all of it.
+        assert_eq!(groups("10.1.2.0-10.1.3.0", GroupMatch::MostSpecific), "dc1");
+    }
 }
@@ -1,14 +1,20 @@
 use std::{
     collections::HashSet,
     convert,
+    fmt::{self, Display, Formatter},
     hash::{Hash, RandomState},
-    mem,
+    iter, mem,
+    net::IpAddr,
+    rc::Rc,
     str::FromStr,
 };
 
+use anyhow::bail;
+use either::Either;
+use ipnet::IpNet;
 use itertools::Itertools;
 
-use crate::source::Source;
+use crate::{addr_or_net::AddrOrNet, source::Source};
 
 pub enum Input<T> {
     Memory(Vec<T>),
@@ -18,8 +24,7 @@ pub enum Input<T> {
 impl<T> Input<T> {
     pub fn load_to_memory(&mut self) -> Result<(), anyhow::Error>
     where
-        T: FromStr,
-        <T as FromStr>::Err: Into<anyhow::Error>,
+        T: InputValue,
     {
         match self {
             Self::Memory(_values) => Ok(()),
@@ -36,8 +41,7 @@ impl<T> Input<T> {
 
     pub fn sort(&mut self) -> Result<(), anyhow::Error>
     where
-        T: FromStr + Ord,
-        <T as FromStr>::Err: Into<anyhow::Error>,
+        T: InputValue + Ord,
     {
         self.load_to_memory()?;
         match self {
@@ -51,8 +55,7 @@ impl<T> Input<T> {
 
     pub fn unique(&mut self) -> Result<(), anyhow::Error>
     where
-        T: FromStr + Eq + Hash,
-        <T as FromStr>::Err: Into<anyhow::Error>,
+        T: InputValue + Eq + Hash,
     {
         self.load_to_memory()?;
         match self {
@@ -69,8 +72,7 @@ impl<T> Input<T> {
 
 impl<T> IntoIterator for Input<T>
 where
-    T: FromStr,
-    <T as FromStr>::Err: Into<anyhow::Error>,
+    T: InputValue,
 {
     type Item = Result<T, anyhow::Error>;
     type IntoIter = IntoIter<T>;
@@ -84,14 +86,13 @@ where
                     .map(|s| s.into_iter())
                     .flatten_ok()
                     .filter(|l| match l {
-                        Ok(Ok(l)) => !l.trim().is_empty(),
+                        Ok(Ok(l)) => !l.is_blank(),
                         _ => true,
                     })
                     .map(|source| {
                         source.and_then(convert::identity).and_then(|l| {
-                            T::from_str(l.trim()).map_err(|err| {
-                                err.into()
-                                    .context(format!("invalid address or network: {l}"))
+                            l.parse().map_err(|err| {
+                                err.context(format!("invalid address or network: {l}"))
                             })
                         })
                     })
@@ -100,6 +101,78 @@ where
     }
 }
 
+/// Parse the sources, keeping track of the location of each value.
+pub fn located<T>(
+    sources: Vec<Source>,
+) -> impl Iterator<Item = Result<(Location, T), anyhow::Error>>
+where
+    T: InputValue,
+{
+    sources.into_iter().flat_map(|source| {
+        let name = Rc::<str>::from(source.to_string());
+        match source.into_iter() {
+            Ok(lines) => Either::Left(lines.enumerate().filter_map(move |(index, line)| {
+                let location = Location {
+                    source: Rc::clone(&name),
+                    line: index + 1,
+                };
+                match line {
+                    Ok(line) if line.is_blank() => None,
+                    Ok(line) => Some(line.parse().map(|value| (location.clone(), value)).map_err(
+                        |err| {
+                            err.context(format!("invalid address or network at {location}: {line}"))
+                        },
+                    )),
+                    Err(err) => Some(Err(err)),
+                }
+            })),
+            Err(err) => Either::Right(iter::once(Err(err))),
+        }
+    })
+}
+
+/// Value read from sources, parsed from text or converted from group entries
+/// (accepted as if they were written as text).
+pub trait InputValue: FromStr<Err: Into<anyhow::Error>> {
+    fn from_entry(entry: AddrOrNet) -> Result<Self, anyhow::Error>;
+}
+
+impl InputValue for AddrOrNet {
+    fn from_entry(entry: AddrOrNet) -> Result<Self, anyhow::Error> {
+        Ok(entry)
+    }
+}
+
+impl InputValue for IpNet {
+    fn from_entry(entry: AddrOrNet) -> Result<Self, anyhow::Error> {
+        match entry {
+            AddrOrNet::IpNet(net) => Ok(net),
+            _ => bail!("not a network"),
+        }
+    }
+}
+
+impl InputValue for IpAddr {
+    fn from_entry(entry: AddrOrNet) -> Result<Self, anyhow::Error> {
+        match entry {
+            AddrOrNet::IpAddr(addr) => Ok(addr),
+            _ => bail!("not a single address"),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Location {
+    pub source: Rc<str>,
+    pub line: usize,
+}
+
+impl Display for Location {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.source, self.line)
+    }
+}
+
 pub enum IntoIter<T> {
     Memory(std::vec::IntoIter<T>),
     Lazy(Box<dyn Iterator<Item = Result<T, anyhow::Error>>>),
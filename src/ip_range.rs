@@ -0,0 +1,269 @@
+use std::{
+    fmt::{Display, Formatter},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+use anyhow::{Context, Error as AnyError, bail};
+use ipnet::IpNet;
+
+use crate::addr_count::AddrCount;
+
+#[derive(Ord, PartialOrd, Eq, PartialEq, Hash, Copy, Clone, Debug)]
+pub struct IpRange {
+    start: IpAddr,
+    end: IpAddr,
+}
+
+impl IpRange {
+    pub fn new(start: IpAddr, end: IpAddr) -> Result<Self, AnyError> {
+        if start.is_ipv4() != end.is_ipv4() {
+            bail!("range bounds must be of the same IP version");
+        }
+        if start > end {
+            bail!("range start is greater than range end");
+        }
+        Ok(Self { start, end })
+    }
+
+    pub fn start(&self) -> IpAddr {
+        self.start
+    }
+
+    pub fn end(&self) -> IpAddr {
+        self.end
+    }
+
+    pub fn size(&self) -> AddrCount {
+        AddrCount::of_range(self)
+    }
+
+    /// Whether the other range is entirely part of this one.
+    pub fn contains(&self, other: &IpRange) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    /// Whether the ranges share at least one address (never the case for
+    /// different IP versions, as all IPv4 addresses sort before IPv6 ones).
+    pub fn overlaps(&self, other: &IpRange) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    /// Prefix length of the smallest network covering the whole range.
+    pub fn covering_prefix_len(&self) -> u8 {
+        let max_prefix_len = max_prefix_len(self.start);
+        // Bounds share the prefix of every network containing both.
+        ((to_bits(self.start) ^ to_bits(self.end)).leading_zeros() - (128 - max_prefix_len as u32))
+            as u8
+    }
+
+    /// Decompose the range into the minimal list of aligned networks.
+    pub fn nets(&self) -> Nets {
+        Nets {
+            next: Some(to_bits(self.start)),
+            end: to_bits(self.end),
+            max_prefix_len: max_prefix_len(self.start),
+        }
+    }
+}
+
+impl From<IpAddr> for IpRange {
+    fn from(addr: IpAddr) -> Self {
+        Self {
+            start: addr,
+            end: addr,
+        }
+    }
+}
+
+impl From<IpNet> for IpRange {
+    fn from(net: IpNet) -> Self {
+        Self {
+            start: net.network(),
+            end: net.broadcast(),
+        }
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = AnyError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let (start, end) = input.split_once('-').context("missing range separator")?;
+        let start = start.trim().parse::<IpAddr>()?;
+        let end = end.trim();
+        let end = match end.parse::<IpAddr>() {
+            Ok(end) => end,
+            // Shorthand notation, only replacing the last octet (IPv4) or segment (IPv6).
+            Err(_) => match start {
+                IpAddr::V4(start) => {
+                    let mut octets = start.octets();
+                    octets[3] = end.parse().context("invalid range end")?;
+                    IpAddr::V4(Ipv4Addr::from(octets))
+                }
+                IpAddr::V6(start) => {
+                    let mut segments = start.segments();
+                    segments[7] = u16::from_str_radix(end, 16).context("invalid range end")?;
+                    IpAddr::V6(Ipv6Addr::from(segments))
+                }
+            },
+        };
+        Self::new(start, end)
+    }
+}
+
+impl Display for IpRange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+pub struct Nets {
+    next: Option<u128>,
+    end: u128,
+    max_prefix_len: u8,
+}
+
+impl Iterator for Nets {
+    type Item = IpNet;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.next?;
+        // Largest block aligned on the start address that doesn't go past the end.
+        let mut host_bits = start.trailing_zeros().min(self.max_prefix_len as u32);
+        while host_bits > 0 && start + host_mask(host_bits) > self.end {
+            host_bits -= 1;
+        }
+        let last = start + host_mask(host_bits);
+        self.next = (last < self.end).then(|| last + 1);
+        Some(
+            IpNet::new(
+                from_bits(start, self.max_prefix_len),
+                self.max_prefix_len - host_bits as u8,
+            )
+            .expect("unexpected invalid prefix length"),
+        )
+    }
+}
+
+/// Following address, if any.
+pub fn succ(addr: IpAddr) -> Option<IpAddr> {
+    match addr {
+        IpAddr::V4(addr) => u32::from(addr)
+            .checked_add(1)
+            .map(|a| Ipv4Addr::from(a).into()),
+        IpAddr::V6(addr) => u128::from(addr)
+            .checked_add(1)
+            .map(|a| Ipv6Addr::from(a).into()),
+    }
+}
+
+/// Preceding address, if any.
+pub fn pred(addr: IpAddr) -> Option<IpAddr> {
+    match addr {
+        IpAddr::V4(addr) => u32::from(addr)
+            .checked_sub(1)
+            .map(|a| Ipv4Addr::from(a).into()),
+        IpAddr::V6(addr) => u128::from(addr)
+            .checked_sub(1)
+            .map(|a| Ipv6Addr::from(a).into()),
+    }
+}
+
+pub fn to_bits(addr: IpAddr) -> u128 {
+    match addr {
+        IpAddr::V4(addr) => u32::from(addr) as u128,
+        IpAddr::V6(addr) => u128::from(addr),
+    }
+}
+
+pub fn from_bits(bits: u128, max_prefix_len: u8) -> IpAddr {
+    if max_prefix_len == 32 {
+        IpAddr::V4(Ipv4Addr::from(bits as u32))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(bits))
+    }
+}
+
+pub fn max_prefix_len(addr: IpAddr) -> u8 {
+    match addr {
+        IpAddr::V4(_) => 32,
+        IpAddr::V6(_) => 128,
+    }
+}
+
+fn host_mask(host_bits: u32) -> u128 {
+    u128::MAX.checked_shr(128 - host_bits).unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IpRange;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            "10.0.0.5-10.0.0.20".parse::<IpRange>().unwrap(),
+            "10.0.0.5-20".parse::<IpRange>().unwrap()
+        );
+        assert_eq!(
+            "2001:db8::1-2001:db8::ff".parse::<IpRange>().unwrap(),
+            "2001:db8::1-ff".parse::<IpRange>().unwrap()
+        );
+        assert!("10.0.0.20-10.0.0.5".parse::<IpRange>().is_err());
+        assert!("10.0.0.1-::1".parse::<IpRange>().is_err());
+        assert!("10.0.0.1-256".parse::<IpRange>().is_err());
+    }
+
+    #[test]
+    fn nets() {
+        assert_eq!(
+            "10.0.0.5-20"
+                .parse::<IpRange>()
+                .unwrap()
+                .nets()
+                .collect::<Vec<_>>(),
+            [
+                "10.0.0.5/32".parse().unwrap(),
+                "10.0.0.6/31".parse().unwrap(),
+                "10.0.0.8/29".parse().unwrap(),
+                "10.0.0.16/30".parse().unwrap(),
+                "10.0.0.20/32".parse().unwrap(),
+            ]
+        );
+        assert_eq!(
+            "0.0.0.0-255.255.255.255"
+                .parse::<IpRange>()
+                .unwrap()
+                .nets()
+                .collect::<Vec<_>>(),
+            ["0.0.0.0/0".parse().unwrap()]
+        );
+        assert_eq!(
+            "::-ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"
+                .parse::<IpRange>()
+                .unwrap()
+                .nets()
+                .collect::<Vec<_>>(),
+            ["::/0".parse().unwrap()]
+        );
+        assert_eq!(
+            "0.0.0.1-255.255.255.255"
+                .parse::<IpRange>()
+                .unwrap()
+                .nets()
+                .count(),
+            32
+        );
+    }
+
+    #[test]
+    fn covering_prefix_len() {
+        let prefix_len = |input: &str| input.parse::<IpRange>().unwrap().covering_prefix_len();
+        assert_eq!(prefix_len("10.0.0.1-5"), 29);
+        assert_eq!(prefix_len("10.0.0.0-255"), 24);
+        assert_eq!(prefix_len("10.0.0.5-10.0.0.5"), 32);
+        assert_eq!(prefix_len("0.0.0.0-255.255.255.255"), 0);
+        assert_eq!(prefix_len("2001:db8::-2001:db8::1:0"), 111);
+    }
+}
@@ -0,0 +1,218 @@
+use std::net::IpAddr;
+
+use ipnet::IpNet;
+use itertools::Itertools;
+
+use crate::{
+    addr_or_net::AddrOrNet,
+    input::Input,
+    ip_range::{IpRange, pred, succ, to_bits},
+    source::Source,
+};
+
+/// Sorted list of disjoint and non-adjacent address ranges.
+#[derive(Eq, PartialEq, Clone, Default, Debug)]
+pub struct IpSet {
+    ranges: Vec<IpRange>,
+}
+
+impl IpSet {
+    pub fn load(sources: Vec<Source>) -> Result<Self, anyhow::Error> {
+        Input::<AddrOrNet>::Lazy(sources)
+            .into_iter()
+            .map_ok(AddrOrNet::range)
+            .collect()
+    }
+
+    pub fn ranges(&self) -> &[IpRange] {
+        &self.ranges
+    }
+
+    /// Minimal list of aligned networks covering the set.
+    pub fn nets(&self) -> impl Iterator<Item = IpNet> + '_ {
+        self.ranges.iter().flat_map(IpRange::nets)
+    }
+
+    /// Whether any address of the range belongs to the set.
+    pub fn overlaps(&self, range: &IpRange) -> bool {
+        let index = self.ranges.partition_point(|r| r.end() < range.start());
+        self.ranges
+            .get(index)
+            .is_some_and(|r| r.start() <= range.end())
+    }
+
+    /// Addresses present in both sets.
+    pub fn intersection(&self, other: &IpSet) -> IpSet {
+        let mut ranges = Vec::new();
+        let (mut lhs, mut rhs) = (
+            self.ranges.iter().peekable(),
+            other.ranges.iter().peekable(),
+        );
+        while let (Some(l), Some(r)) = (lhs.peek(), rhs.peek()) {
+            // Bounds of different IP versions never satisfy this condition.
+            let (start, end) = (l.start().max(r.start()), l.end().min(r.end()));
+            if start <= end {
+                ranges.push(new_range(start, Some(end)));
+            }
+            if l.end() < r.end() {
+                lhs.next();
+            } else {
+                rhs.next();
+            }
+        }
+        Self { ranges }
+    }
+
+    /// Addresses of the set not present in the other one.
+    pub fn difference(&self, other: &IpSet) -> IpSet {
+        let mut ranges = Vec::with_capacity(self.ranges.len());
+        let mut others = other.ranges.iter().peekable();
+        for range in &self.ranges {
+            // Ranges ending before the current one can't overlap the next ones either.
+            while others.next_if(|o| o.end() < range.start()).is_some() {}
+
+            let mut start = Some(range.start());
+            for other in others.clone() {
+                let Some(current) = start else {
+                    break;
+                };
+                if other.start() > range.end() {
+                    break;
+                }
+                if other.start() > current {
+                    ranges.push(new_range(current, pred(other.start())));
+                }
+                start = succ(other.end()).filter(|_| other.end() < range.end());
+            }
+            if let Some(start) = start {
+                ranges.push(new_range(start, Some(range.end())));
+            }
+        }
+        Self { ranges }
+    }
+}
+
+impl FromIterator<IpRange> for IpSet {
+    fn from_iter<I: IntoIterator<Item = IpRange>>(iter: I) -> Self {
+        let mut ranges = iter.into_iter().collect::<Vec<_>>();
+        ranges.sort_unstable();
+
+        let mut merged = Vec::<IpRange>::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last)
+                    if last.start().is_ipv4() == range.start().is_ipv4()
+                        && to_bits(range.start()) <= to_bits(last.end()).saturating_add(1) =>
+                {
+                    if range.end() > last.end() {
+                        *last = new_range(last.start(), Some(range.end()));
+                    }
+                }
+                _ => merged.push(range),
+            }
+        }
+        Self { ranges: merged }
+    }
+}
+
+fn new_range(start: IpAddr, end: Option<IpAddr>) -> IpRange {
+    IpRange::new(start, end.expect("unexpected range bound")).expect("unexpected invalid range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IpSet;
+    use crate::addr_or_net::AddrOrNet;
+
+    #[test]
+    fn collapse() {
+        let set = [
+            "10.0.0.8/29",
+            "10.0.0.0-10.0.0.7",
+            "10.0.0.20-30",
+            "10.0.0.16/30",
+            "::/0",
+            "192.168.0.1-1",
+            "2001:db8::/32",
+        ]
+        .into_iter()
+        .map(|r| r.parse::<AddrOrNet>().unwrap().range())
+        .collect::<IpSet>();
+        assert_eq!(
+            set.ranges(),
+            [
+                "10.0.0.0-10.0.0.30".parse().unwrap(),
+                "192.168.0.1-1".parse().unwrap(),
+                "::-ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff"
+                    .parse()
+                    .unwrap(),
+            ]
+        );
+    }
+
+    #[test]
+    fn difference() {
+        let set = |ranges: &[&str]| {
+            ranges
+                .iter()
+                .map(|r| r.parse::<AddrOrNet>().unwrap().range())
+                .collect::<IpSet>()
+        };
+        assert_eq!(
+            set(&["10.0.0.0/24", "10.0.2.0/24", "::/0"])
+                .difference(&set(&[
+                    "10.0.0.64/26",
+                    "10.0.0.255",
+                    "10.0.1.0-10.0.2.127",
+                    "::/1"
+                ]))
+                .nets()
+                .collect::<Vec<_>>(),
+            [
+                "10.0.0.0/26".parse().unwrap(),
+                "10.0.0.128/26".parse().unwrap(),
+                "10.0.0.192/27".parse().unwrap(),
+                "10.0.0.224/28".parse().unwrap(),
+                "10.0.0.240/29".parse().unwrap(),
+                "10.0.0.248/30".parse().unwrap(),
+                "10.0.0.252/31".parse().unwrap(),
+                "10.0.0.254/32".parse().unwrap(),
+                "10.0.2.128/25".parse().unwrap(),
+                "8000::/1".parse().unwrap(),
+            ]
+        );
+        assert_eq!(
+            set(&["0.0.0.0/0"]).difference(&set(&["0.0.0.0/1", "128.0.0.0/1"])),
+            IpSet::default()
+        );
+    }
+
+    #[test]
+    fn intersection() {
+        let set = |ranges: &[&str]| {
+            ranges
+                .iter()
+                .map(|r| r.parse::<AddrOrNet>().unwrap().range())
+                .collect::<IpSet>()
+        };
+        let lhs = set(&[
+            "10.0.0.0/24",
+            "10.0.2.0/24",
+            "192.168.0.0/16",
+            "2001:db8::/32",
+        ]);
+        let rhs = set(&["10.0.0.128-10.0.2.63", "192.168.1.1", "::/0"]);
+        assert_eq!(
+            lhs.intersection(&rhs).nets().collect::<Vec<_>>(),
+            [
+                "10.0.0.128/25".parse().unwrap(),
+                "10.0.2.0/26".parse().unwrap(),
+                "192.168.1.1/32".parse().unwrap(),
+                "2001:db8::/32".parse().unwrap(),
+            ]
+        );
+        assert!(lhs.overlaps(&"10.0.2.255".parse::<AddrOrNet>().unwrap().range()));
+        assert!(!lhs.overlaps(&"10.0.1.0-10.0.1.255".parse().unwrap()));
+        assert!(!lhs.overlaps(&"::1".parse::<AddrOrNet>().unwrap().range()));
+    }
+}
@@ -1,24 +1,46 @@
 #![warn(unused_crate_dependencies)]
 
+mod addr_count;
 mod addr_or_net;
-mod auto_net;
 mod commands;
 mod config;
 mod group;
 mod input;
+mod ip_range;
+mod ip_set;
 mod options;
+mod output;
+mod prefix_trie;
 mod source;
+mod special_purpose;
+mod template;
+
+use std::process::ExitCode;
 
 use anyhow::bail;
 use clap::Parser;
 
 use crate::{
-    config::Config,
-    options::{Command, Options},
+    commands::{group::Summary, info::Table, merge::Budget},
+    config::LazyConfig,
+    options::{Command, GroupsCommand, Options},
+    output::Printer,
     source::Source,
 };
 
-fn main() -> Result<(), anyhow::Error> {
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            // Distinct from the code of commands reporting a negative result
+            // (no match, differences or conflicts), like grep and diff.
+            ExitCode::from(2)
+        }
+    }
+}
+
+fn run() -> Result<ExitCode, anyhow::Error> {
     let options = Options::parse();
 
     let sources = if options.inputs.is_empty() && options.args.is_empty() {
@@ -36,18 +58,74 @@ fn main() -> Result<(), anyhow::Error> {
         options
             .args
             .into_iter()
-            .map(Source::Arg)
+            .map(Source::from_arg)
             .chain(options.inputs)
             .collect()
     };
+    let mut configuration = LazyConfig::new(options.config_path);
+    // The template gets its own copy, with groups loaded beforehand so that
+    // their commands only run once.
+    let template_configuration = match &options.format {
+        Some(template) if template.uses_groups() => {
+            group::index_groups(configuration.get_mut()?)?;
+            configuration.get_mut()?.cloned()
+        }
+        _ => None,
+    };
+    let mut resolve_groups = |mut sources: Vec<Source>| -> Result<_, anyhow::Error> {
+        if sources.iter().any(|s| matches!(s, Source::Group { .. })) {
+            source::resolve_groups(&mut sources, configuration.get_mut()?)?;
+        }
+        Ok(sources)
+    };
+    let sources = resolve_groups(sources)?;
 
+    let mut printer = Printer::new(
+        options.output_format,
+        options.format,
+        template_configuration,
+    );
+    let printer = &mut printer;
+    // Negative result (no match, differences or conflicts), exiting with 1.
+    let mut negative = false;
     match options.command {
-        Command::Cat => commands::cat::process_batch(sources, options.sort, options.unique)?,
-        Command::Info { no_padding } => {
-            commands::info::process_batch(sources, !no_padding, options.sort, options.unique)?;
+        Command::Cat => {
+            commands::cat::process_batch(sources, options.sort, options.unique, printer)?
+        }
+        Command::Classify => {
+            commands::classify::process_batch(sources, options.sort, options.unique, printer)?;
+        }
+        Command::Info {
+            no_padding,
+            table,
+            no_header,
+            fields,
+            list_fields,
+        } => {
+            if list_fields {
+                commands::info::list_fields();
+            } else {
+                commands::info::process_batch(
+                    sources,
+                    fields,
+                    configuration.into_inner()?,
+                    !no_padding,
+                    table.then_some(Table { header: !no_header }),
+                    options.sort,
+                    options.unique,
+                    printer,
+                )?;
+            }
         }
         Command::Net { prefix_len, cidr } => {
-            commands::net::process_batch(sources, prefix_len, cidr, options.sort, options.unique)?;
+            commands::net::process_batch(
+                sources,
+                prefix_len,
+                cidr,
+                options.sort,
+                options.unique,
+                printer,
+            )?;
         }
         Command::Subnet { prefix_len, cidr } => {
             commands::subnet::process_batch(
@@ -56,31 +134,154 @@ fn main() -> Result<(), anyhow::Error> {
                 cidr,
                 options.sort,
                 options.unique,
+                printer,
             )?;
         }
         Command::Hosts { no_all } => {
-            commands::hosts::process_batch(sources, !no_all, options.sort, options.unique)?;
+            commands::hosts::process_batch(
+                sources,
+                !no_all,
+                options.sort,
+                options.unique,
+                printer,
+            )?;
         }
-        Command::Merge => {
-            commands::merge::process(sources, options.sort, options.unique)?;
+        Command::Merge {
+            max_prefixes,
+            max_extra,
+        } => {
+            let budget = max_prefixes
+                .map(Budget::Prefixes)
+                .or(max_extra.map(Budget::Extra));
+            commands::merge::process(sources, budget, printer)?;
         }
-        Command::Filter { query } => commands::filter::process_batch(
+        Command::Overlap => negative = commands::overlap::process(sources, printer)?,
+        Command::Range { to_range, .. } => {
+            commands::range::process_batch(
+                sources,
+                to_range,
+                options.sort,
+                options.unique,
+                printer,
+            )?;
+        }
+        Command::Complement { within } => commands::complement::process(sources, within, printer)?,
+        Command::Contains {
+            haystacks,
+            haystack_groups,
+            quiet,
+            invert_match,
+            count,
+        } => {
+            if uses_stdin(&haystacks) && uses_stdin(&sources) {
+                bail!("stdin can't be used for both inputs and haystack");
+            }
+            let haystacks = resolve_groups(haystacks)?;
+            let configuration = configuration.into_inner_if(!haystack_groups.is_empty())?;
+            negative = !commands::contains::process_batch(
+                sources,
+                haystacks,
+                haystack_groups,
+                configuration,
+                quiet,
+                invert_match,
+                count,
+                options.sort,
+                options.unique,
+                printer,
+            )?;
+        }
+        Command::Diff { to } => {
+            if uses_stdin(&to) && uses_stdin(&sources) {
+                bail!("stdin can't be used for both inputs and compared list");
+            }
+            negative = commands::diff::process(sources, resolve_groups(to)?, printer)?;
+        }
+        Command::Exclude {
+            exclusions,
+            exclusion_groups,
+        } => {
+            if uses_stdin(&exclusions) && uses_stdin(&sources) {
+                bail!("stdin can't be used for both inputs and exclusions");
+            }
+            let exclusions = resolve_groups(exclusions)?;
+            let configuration = configuration.into_inner_if(!exclusion_groups.is_empty())?;
+            commands::exclude::process(
+                sources,
+                exclusions,
+                exclusion_groups,
+                configuration,
+                printer,
+            )?;
+        }
+        Command::Intersect {
+            with,
+            with_groups,
+            entries,
+        } => {
+            if uses_stdin(&with) && uses_stdin(&sources) {
+                bail!("stdin can't be used for both inputs and intersected list");
+            }
+            let with = resolve_groups(with)?;
+            let configuration = configuration.into_inner_if(!with_groups.is_empty())?;
+            commands::intersect::process(
+                sources,
+                with,
+                with_groups,
+                entries,
+                configuration,
+                printer,
+            )?;
+        }
+        Command::Filter { query, group_match } => commands::filter::process_batch(
             sources,
             query,
-            Config::load(options.config_path)?,
+            configuration.into_inner()?,
+            group_match,
             options.sort,
             options.unique,
+            printer,
         )?,
-        Command::Group { exit_no_match } => {
+        Command::Group {
+            exit_no_match,
+            group_match,
+            annotate,
+            default_label,
+            summary,
+            percent,
+        } => {
             commands::group::process_batch(
                 sources,
-                Config::load(options.config_path)?,
+                configuration.into_inner()?,
                 exit_no_match,
+                group_match,
+                annotate,
+                default_label,
+                summary.then_some(Summary { percent }),
                 options.sort,
                 options.unique,
+                printer,
             )?;
         }
+        Command::Groups { command } => match command {
+            GroupsCommand::List => commands::groups::list(configuration.into_inner()?, printer)?,
+            GroupsCommand::Show { name, merge } => {
+                commands::groups::show(&name, merge, configuration.into_inner()?, printer)?;
+            }
+            GroupsCommand::Export => {
+                commands::groups::export(configuration.into_inner()?, printer)?
+            }
+        },
     }
+    printer.finish();
+
+    Ok(if negative {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
 
-    Ok(())
+fn uses_stdin(sources: &[Source]) -> bool {
+    sources.iter().any(|s| matches!(s, Source::Stdin))
 }
@@ -1,8 +1,11 @@
 use std::path::PathBuf;
 
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand, builder::RangedU64ValueParser};
 
-use crate::source::Source;
+use crate::{
+    addr_or_net::AddrOrNet, commands::info::Field, group::GroupMatch, output::OutputFormat,
+    source::Source, template::Template,
+};
 
 #[derive(Parser, Debug)]
 #[command(version, about)]
@@ -13,7 +16,8 @@ pub struct Options {
     pub config_path: Option<PathBuf>,
     #[command(subcommand)]
     pub command: Command,
-    /// File path(s) to read from ('-' for stdin).
+    /// File path(s) to read from ('-' for stdin, '@group:name' for the networks
+    /// of a configuration group).
     #[arg(short, long = "input", global = true)]
     pub inputs: Vec<Source>,
     /// Sort results before displaying (allocation required).
@@ -22,7 +26,16 @@ pub struct Options {
     /// Remove duplicates before displaying (allocation required).
     #[arg(short, long, alias = "uniq", global = true)]
     pub unique: bool,
-    /// Additional input(s) to process.
+    /// Format of the results.
+    #[arg(short, long, global = true, value_enum, default_value_t)]
+    pub output_format: OutputFormat,
+    /// Template rendered for each result, e.g. "{network}/{cidr} {group}"
+    /// (placeholders are output columns, info fields or filter placeholders,
+    /// in this order of precedence).
+    #[arg(short = 'F', long, global = true, conflicts_with = "output_format")]
+    pub format: Option<Template>,
+    /// Additional input(s) to process ('@group:name' for the networks of a
+    /// configuration group).
     #[arg(global = true)]
     pub args: Vec<String>,
 }
@@ -31,11 +44,27 @@ pub struct Options {
 pub enum Command {
     /// Parse, validate and concatenate IP addresses or networks.
     Cat,
+    /// Classify IP addresses or networks using the IANA special-purpose
+    /// registries.
+    Classify,
     /// Describe an IP address or network.
     Info {
         /// Disable column alignment padding.
         #[arg(short = 'P', long)]
         no_padding: bool,
+        /// Display one row per input and one column per field (text output
+        /// only).
+        #[arg(short, long)]
+        table: bool,
+        /// Don't display the table header.
+        #[arg(short = 'H', long, requires = "table")]
+        no_header: bool,
+        /// Comma separated list of fields to display (see --list-fields).
+        #[arg(short, long, value_delimiter = ',')]
+        fields: Vec<Field>,
+        /// List available fields.
+        #[arg(short, long)]
+        list_fields: bool,
     },
     /// Get the network address of an IP address.
     Net {
@@ -62,17 +91,160 @@ pub enum Command {
         no_all: bool,
     },
     /// Recursively merge consecutive and aligned subnets into supernets.
-    Merge,
+    Merge {
+        /// Summarize into at most this number of networks (one per IP version
+        /// at least), covering as few additional addresses as possible.
+        #[arg(
+            short = 'n',
+            long,
+            conflicts_with = "max_extra",
+            value_parser = RangedU64ValueParser::<usize>::new().range(1..)
+        )]
+        max_prefixes: Option<usize>,
+        /// Summarize into as few networks as possible, covering at most this
+        /// number of additional addresses.
+        #[arg(short = 'x', long)]
+        max_extra: Option<u128>,
+    },
+    /// Report duplicated, nested and overlapping entries, exiting with code 1
+    /// if any is found (2 on errors).
+    #[command(alias = "lint")]
+    Overlap,
+    /// Convert between networks and ranges of IP addresses.
+    Range {
+        /// Decompose each input into the minimal list of aligned networks.
+        #[arg(short = 'c', long, required_unless_present = "to_range")]
+        to_cidr: bool,
+        /// Collapse inputs into contiguous ranges.
+        #[arg(short = 'r', long, conflicts_with = "to_cidr")]
+        to_range: bool,
+    },
+    /// Display the networks not covered by the inputs.
+    Complement {
+        /// Network(s) or range(s) to compute the complement within
+        /// (whole address space of each IP version present in the inputs if not
+        /// specified).
+        #[arg(short, long)]
+        within: Vec<AddrOrNet>,
+    },
+    /// Display the inputs contained in any of the haystack entries, exiting
+    /// with code 1 if none is (2 on errors).
+    #[command(group(ArgGroup::new("haystack").required(true).multiple(true)))]
+    Contains {
+        /// File path(s) ('-' for stdin), groups ('@group:name') or
+        /// address(es), network(s) and range(s) to search in.
+        #[arg(
+            short = 'H',
+            long = "haystack",
+            value_parser = Source::from_file_or_arg,
+            group = "haystack"
+        )]
+        haystacks: Vec<Source>,
+        /// Configuration group(s) to search in.
+        #[arg(short = 'g', long = "haystack-group", group = "haystack")]
+        haystack_groups: Vec<String>,
+        /// Don't display anything, only exit with the appropriate code.
+        #[arg(short, long)]
+        quiet: bool,
+        /// Select inputs not contained in any of the haystack entries.
+        #[arg(short = 'v', long)]
+        invert_match: bool,
+        /// Only display the number of selected inputs.
+        #[arg(short, long)]
+        count: bool,
+    },
+    /// Display the address space gained ('+') and lost ('-') from the inputs to
+    /// another list, exiting with code 1 if they differ (2 on errors).
+    Diff {
+        /// File path(s) ('-' for stdin), groups ('@group:name') or
+        /// address(es), network(s) and range(s) to compare to.
+        #[arg(short, long = "to", value_parser = Source::from_file_or_arg, required = true)]
+        to: Vec<Source>,
+    },
+    /// Remove networks from the inputs and display the remaining ones.
+    #[command(group(ArgGroup::new("excluded").required(true).multiple(true)))]
+    Exclude {
+        /// File path(s) ('-' for stdin), groups ('@group:name') or
+        /// address(es), network(s) and range(s) to exclude.
+        #[arg(
+            short = 'x',
+            long = "exclude",
+            value_parser = Source::from_file_or_arg,
+            group = "excluded"
+        )]
+        exclusions: Vec<Source>,
+        /// Configuration group(s) to exclude.
+        #[arg(short = 'g', long = "exclude-group", group = "excluded")]
+        exclusion_groups: Vec<String>,
+    },
+    /// Display the address space shared by the inputs and another list.
+    #[command(group(ArgGroup::new("others").required(true).multiple(true)))]
+    Intersect {
+        /// File path(s) ('-' for stdin), groups ('@group:name') or
+        /// address(es), network(s) and range(s) to intersect with.
+        #[arg(short, long = "with", value_parser = Source::from_file_or_arg, group = "others")]
+        with: Vec<Source>,
+        /// Configuration group(s) to intersect with.
+        #[arg(short = 'g', long = "with-group", group = "others")]
+        with_groups: Vec<String>,
+        /// Display the original overlapping entries of both sides instead
+        /// ('<' for inputs, '>' for others).
+        #[arg(short, long)]
+        entries: bool,
+    },
     /// Filter addresses and networks based on a query.
     Filter {
         /// Add network and broadcast address if available.
         #[arg(short, long)]
         query: String,
+        /// Group given by the group placeholder (every matching group is given
+        /// by the groups placeholder, 'all' is rejected).
+        #[arg(short = 'm', long = "match", value_enum, default_value_t)]
+        group_match: GroupMatch,
     },
-    /// Get the first matching group of IP addresses or networks.
+    /// Get the matching group(s) of IP addresses or networks.
     Group {
         /// Exit on no group match.
         #[arg(short = 'f', long)]
         exit_no_match: bool,
+        /// Group(s) to display for each input.
+        #[arg(short = 'm', long = "match", value_enum, default_value_t)]
+        group_match: GroupMatch,
+        /// Display each input followed by a tab and its comma separated
+        /// group(s).
+        #[arg(short, long)]
+        annotate: bool,
+        /// Label used for inputs matching no group.
+        #[arg(short, long, conflicts_with = "exit_no_match")]
+        default_label: Option<String>,
+        /// Count inputs per group and per network within each group instead
+        /// (sorted by decreasing count with --sort).
+        #[arg(short = 'S', long, conflicts_with_all = ["annotate", "default_label", "exit_no_match"])]
+        summary: bool,
+        /// Add the percentage of inputs to the summary.
+        #[arg(short, long, requires = "summary")]
+        percent: bool,
+    },
+    /// Inspect the groups defined in the configuration.
+    Groups {
+        #[command(subcommand)]
+        command: GroupsCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum GroupsCommand {
+    /// List groups with their number of networks and source.
+    List,
+    /// Display the networks of a group.
+    Show {
+        /// Name of the group.
+        name: String,
+        /// Merge overlapping and adjacent networks.
+        #[arg(short, long)]
+        merge: bool,
     },
+    /// Export every group with its resolved networks as a configuration
+    /// snapshot (TOML, or JSON with '--output-format json').
+    Export,
 }
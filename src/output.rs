@@ -0,0 +1,360 @@
+use std::fmt::{self, Display, Formatter};
+
+use anyhow::bail;
+use clap::ValueEnum;
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{addr_count::AddrCount, addr_or_net::AddrOrNet, config::Config, template::Template};
+
+#[derive(ValueEnum, Copy, Clone, Default, Debug)]
+pub enum OutputFormat {
+    /// Human readable text.
+    #[default]
+    Text,
+    /// Array of JSON objects.
+    Json,
+    /// One JSON object per line.
+    Ndjson,
+    /// Comma separated values, with a header.
+    Csv,
+    /// Tab separated values, with a header.
+    Tsv,
+}
+
+pub type Record = Vec<(&'static str, Value)>;
+
+/// Record columns of every command (besides info fields), to validate templates
+/// before any result is displayed.
+pub const COLUMNS: &[&str] = &[
+    "address",
+    "change",
+    "count",
+    "entry",
+    "first",
+    "globally_reachable",
+    "group",
+    "host",
+    "input",
+    "last",
+    "location",
+    "name",
+    "nets",
+    "network",
+    "networks",
+    "origin",
+    "other_entry",
+    "other_location",
+    "percent",
+    "prefix",
+    "range",
+    "relation",
+    "rfc",
+    "scope",
+    "side",
+    "source",
+    "subnet",
+    "type",
+    "version",
+];
+
+#[derive(Clone, Debug)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Number(u128),
+    Float(f64),
+    /// Number of addresses, always a JSON string as it can be beyond what most
+    /// parsers store in a double without rounding (up to 2^128).
+    Count(AddrCount),
+    String(String),
+    List(Vec<String>),
+}
+
+impl Value {
+    pub fn string(value: impl Display) -> Self {
+        Value::String(value.to_string())
+    }
+
+    fn write_json(&self, f: &mut impl fmt::Write) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Number(value) => write!(f, "{value}"),
+            Value::Count(value) => write_json_string(f, &value.to_string()),
+            Value::Float(value) => write!(f, "{value}"),
+            Value::String(value) => write_json_string(f, value),
+            Value::List(values) => {
+                write!(f, "[")?;
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_json_string(f, value)?;
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Number(value) => write!(f, "{value}"),
+            Value::Float(value) => write!(f, "{value}"),
+            Value::Count(value) => write!(f, "{value}"),
+            Value::String(value) => write!(f, "{value}"),
+            Value::List(values) => write!(f, "{}", values.iter().join(" ")),
+        }
+    }
+}
+
+/// Display results either as plain text, as structured records or through a
+/// user template.
+pub struct Printer {
+    format: OutputFormat,
+    template: Option<Template>,
+    /// Only used to resolve template group placeholders.
+    configuration: Option<Config>,
+    records: usize,
+    finished: bool,
+}
+
+impl Printer {
+    pub fn new(
+        format: OutputFormat,
+        template: Option<Template>,
+        configuration: Option<Config>,
+    ) -> Self {
+        Self {
+            format,
+            template,
+            configuration,
+            records: 0,
+            finished: false,
+        }
+    }
+
+    pub fn is_text(&self) -> bool {
+        matches!(self.format, OutputFormat::Text) && self.template.is_none()
+    }
+
+    /// Display the text, or the record if a structured format was requested
+    /// (only built if needed), for a result entry.
+    pub fn print(
+        &mut self,
+        entry: impl Into<AddrOrNet>,
+        text: impl Display,
+        record: impl FnOnce() -> Record,
+    ) -> Result<(), anyhow::Error> {
+        self.print_optional_entry(Some(entry.into()), text, record)
+    }
+
+    /// Same as [`Printer::print`] for results not related to an entry (e.g.
+    /// counts).
+    pub fn print_summary(
+        &mut self,
+        text: impl Display,
+        record: impl FnOnce() -> Record,
+    ) -> Result<(), anyhow::Error> {
+        self.print_optional_entry(None, text, record)
+    }
+
+    /// Display a single document instead of results: TOML as text, or JSON.
+    pub fn print_document<T: Serialize>(
+        &mut self,
+        document: impl FnOnce() -> Result<T, anyhow::Error>,
+    ) -> Result<(), anyhow::Error> {
+        // Nothing else to write, even on errors.
+        self.finished = true;
+        if self.template.is_some() {
+            bail!("templates can't be used for this command");
+        }
+        match self.format {
+            OutputFormat::Text => print!("{}", toml::to_string(&document()?)?),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&document()?)?),
+            _ => bail!("only text (TOML) and JSON output are available for this command"),
+        }
+        Ok(())
+    }
+
+    fn print_optional_entry(
+        &mut self,
+        entry: Option<AddrOrNet>,
+        text: impl Display,
+        record: impl FnOnce() -> Record,
+    ) -> Result<(), anyhow::Error> {
+        if let Some(template) = &self.template {
+            println!(
+                "{}",
+                template.render(entry, record, self.configuration.as_mut())?
+            );
+            return Ok(());
+        }
+        match self.format {
+            OutputFormat::Text => println!("{text}"),
+            _ => self.record(record()),
+        }
+        Ok(())
+    }
+
+    fn record(&mut self, record: Record) {
+        let mut buffer = String::with_capacity(256);
+        match self.format {
+            OutputFormat::Text => unreachable!(),
+            OutputFormat::Json | OutputFormat::Ndjson => {
+                if matches!(self.format, OutputFormat::Json) {
+                    buffer.push_str(if self.records == 0 { "[\n  " } else { ",\n  " });
+                }
+                write_json_object(&mut buffer, &record).unwrap();
+                if matches!(self.format, OutputFormat::Ndjson) {
+                    buffer.push('\n');
+                }
+            }
+            OutputFormat::Csv | OutputFormat::Tsv => {
+                let separator = if matches!(self.format, OutputFormat::Csv) {
+                    ","
+                } else {
+                    "\t"
+                };
+                if self.records == 0 {
+                    buffer.push_str(
+                        &record
+                            .iter()
+                            .map(|(key, _)| self.escape(key))
+                            .join(separator),
+                    );
+                    buffer.push('\n');
+                }
+                buffer.push_str(
+                    &record
+                        .iter()
+                        .map(|(_, value)| self.escape(&value.to_string()))
+                        .join(separator),
+                );
+                buffer.push('\n');
+            }
+        }
+        print!("{buffer}");
+        self.records += 1;
+    }
+
+    fn escape(&self, value: &str) -> String {
+        match self.format {
+            OutputFormat::Csv if value.contains([',', '"', '\n', '\r']) => {
+                format!(r#""{}""#, value.replace('"', r#""""#))
+            }
+            OutputFormat::Tsv => value.replace(['\t', '\n', '\r'], " "),
+            _ => value.to_owned(),
+        }
+    }
+
+    /// Terminate the output, required before exiting.
+    pub fn finish(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        if matches!(self.format, OutputFormat::Json) {
+            if self.records == 0 {
+                println!("[]");
+            } else {
+                println!("\n]");
+            }
+        }
+    }
+}
+
+impl Drop for Printer {
+    /// Keep structured output valid when a command fails partway through.
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+/// Typed description of an input.
+pub fn input_record(input: AddrOrNet) -> Record {
+    let range = input.range();
+    vec![
+        ("input", Value::string(input)),
+        ("type", Value::string(input.type_name())),
+        (
+            "version",
+            Value::Number(if input.is_ipv4() { 4 } else { 6 }),
+        ),
+        (
+            "address",
+            match input {
+                AddrOrNet::IpAddr(addr) => Value::string(addr),
+                AddrOrNet::IpNet(net) => Value::string(net.addr()),
+                AddrOrNet::IpRange(_) => Value::Null,
+            },
+        ),
+        (
+            "prefix",
+            input
+                .net()
+                .map_or(Value::Null, |net| Value::Number(net.prefix_len() as u128)),
+        ),
+        ("first", Value::string(range.start())),
+        ("last", Value::string(range.end())),
+    ]
+}
+
+fn write_json_object(f: &mut impl fmt::Write, record: &Record) -> fmt::Result {
+    write!(f, "{{")?;
+    for (i, (key, value)) in record.iter().enumerate() {
+        if i > 0 {
+            write!(f, ",")?;
+        }
+        write_json_string(f, key)?;
+        write!(f, ":")?;
+        value.write_json(f)?;
+    }
+    write!(f, "}}")
+}
+
+fn write_json_string(f: &mut impl fmt::Write, value: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in value.chars() {
+        match c {
+            '"' => write!(f, r#"\""#)?,
+            '\\' => write!(f, r"\\")?,
+            '\n' => write!(f, r"\n")?,
+            '\r' => write!(f, r"\r")?,
+            '\t' => write!(f, r"\t")?,
+            c if c.is_control() => write!(f, r"\u{:04x}", c as u32)?,
+            c => write!(f, "{c}")?,
+        }
+    }
+    write!(f, "\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Value, write_json_object};
+    use crate::addr_count::AddrCount;
+
+    #[test]
+    fn json_numbers() {
+        let json = |value: Value| {
+            let mut buffer = String::new();
+            write_json_object(&mut buffer, &vec![("value", value)]).unwrap();
+            buffer
+        };
+        assert_eq!(json(Value::Number(32)), r#"{"value":32}"#);
+        assert_eq!(
+            json(Value::Count(AddrCount::of_net(&"::/0".parse().unwrap()))),
+            r#"{"value":"340282366920920463463374607431768211456"}"#
+        );
+        assert_eq!(
+            json(Value::Count(AddrCount::of_net(
+                &"10.0.0.0/8".parse().unwrap()
+            ))),
+            r#"{"value":"16777216"}"#
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use ipnet::IpNet;
+
+use crate::ip_range::{IpRange, max_prefix_len, to_bits};
+
+/// Binary trie of networks (one per IP version), to find all the networks
+/// containing an input in a single walk.
+#[derive(Clone, Debug)]
+pub struct PrefixTrie<T> {
+    ipv4: Vec<Node<T>>,
+    ipv6: Vec<Node<T>>,
+}
+
+#[derive(Clone, Debug)]
+struct Node<T> {
+    children: [Option<usize>; 2],
+    values: Vec<T>,
+}
+
+impl<T> Default for Node<T> {
+    fn default() -> Self {
+        Self {
+            children: [None, None],
+            values: Vec::new(),
+        }
+    }
+}
+
+impl<T> PrefixTrie<T> {
+    pub fn new() -> Self {
+        Self {
+            ipv4: vec![Node::default()],
+            ipv6: vec![Node::default()],
+        }
+    }
+
+    pub fn insert(&mut self, net: IpNet, value: T) {
+        let (bits, max_prefix_len) = (to_bits(net.network()), net.max_prefix_len());
+        let nodes = match net {
+            IpNet::V4(_) => &mut self.ipv4,
+            IpNet::V6(_) => &mut self.ipv6,
+        };
+        let mut current = 0;
+        for depth in 0..net.prefix_len() {
+            let bit = bit(bits, depth, max_prefix_len);
+            current = match nodes[current].children[bit] {
+                Some(child) => child,
+                None => {
+                    nodes.push(Node::default());
+                    let child = nodes.len() - 1;
+                    nodes[current].children[bit] = Some(child);
+                    child
+                }
+            };
+        }
+        nodes[current].values.push(value);
+    }
+
+    /// Values of the networks containing the whole range, from the least to
+    /// the most specific, with their prefix length.
+    pub fn containing(&self, range: &IpRange) -> Containing<'_, T> {
+        let start = to_bits(range.start());
+        let max_prefix_len = max_prefix_len(range.start());
+        Containing {
+            nodes: if range.start().is_ipv4() {
+                &self.ipv4
+            } else {
+                &self.ipv6
+            },
+            node: Some(0),
+            depth: 0,
+            max_depth: range.covering_prefix_len(),
+            bits: start,
+            max_prefix_len,
+            values: [].iter(),
+        }
+    }
+}
+
+pub struct Containing<'a, T> {
+    nodes: &'a [Node<T>],
+    node: Option<usize>,
+    depth: u8,
+    max_depth: u8,
+    bits: u128,
+    max_prefix_len: u8,
+    values: std::slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Containing<'a, T> {
+    type Item = (u8, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(value) = self.values.next() {
+                // Values of the previous node, one level up.
+                return Some((self.depth - 1, value));
+            }
+            let node = &self.nodes[self.node?];
+            self.values = node.values.iter();
+            self.node = (self.depth < self.max_depth)
+                .then(|| node.children[bit(self.bits, self.depth, self.max_prefix_len)])
+                .flatten();
+            self.depth += 1;
+        }
+    }
+}
+
+fn bit(bits: u128, depth: u8, max_prefix_len: u8) -> usize {
+    (bits >> (max_prefix_len - 1 - depth) & 1) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrefixTrie;
+    use crate::addr_or_net::AddrOrNet;
+
+    #[test]
+    fn containing() {
+        let mut trie = PrefixTrie::new();
+        for (i, net) in [
+            "0.0.0.0/0",
+            "10.0.0.0/8",
+            "10.1.0.0/16",
+            "10.1.2.3/32",
+            "10.0.0.0/8",
+            "::/0",
+            "2001:db8::/32",
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            trie.insert(net.parse().unwrap(), i);
+        }
+        let containing = |input: &str| {
+            trie.containing(&input.parse::<AddrOrNet>().unwrap().range())
+                .map(|(prefix_len, &i)| (prefix_len, i))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            containing("10.1.2.3"),
+            [(0, 0), (8, 1), (8, 4), (16, 2), (32, 3)]
+        );
+        assert_eq!(
+            containing("10.1.0.0-10.1.255.255"),
+            [(0, 0), (8, 1), (8, 4), (16, 2)]
+        );
+        assert_eq!(containing("10.1.0.0-10.2.0.0"), [(0, 0), (8, 1), (8, 4)]);
+        assert_eq!(containing("192.168.0.1"), [(0, 0)]);
+        assert_eq!(containing("2001:db8::1"), [(0, 5), (32, 6)]);
+        assert_eq!(containing("::/0"), [(0, 5)]);
+    }
+}
@@ -1,18 +1,29 @@
 use std::{
     convert::Infallible,
+    fmt::{self, Display, Formatter},
     fs::File,
     io::{self, BufRead, BufReader, Lines, StdinLock},
     path::PathBuf,
     str::FromStr,
 };
 
-use anyhow::Error as AnyError;
+use anyhow::{Error as AnyError, bail};
+
+use crate::{addr_or_net::AddrOrNet, config::Config, group, input::InputValue};
+
+/// Prefix of the sources reading the networks of a configuration group.
+const GROUP_PREFIX: &str = "@group:";
 
 #[derive(Clone, Debug)]
 pub enum Source {
     File(PathBuf),
     Stdin,
     Arg(String),
+    /// Configuration group, its entries being loaded by [`resolve_groups`].
+    Group {
+        name: String,
+        entries: Option<Vec<AddrOrNet>>,
+    },
 }
 
 impl Source {
@@ -21,8 +32,42 @@ impl Source {
             Source::File(path) => IntoIter::File(BufReader::new(File::open(path)?).lines()),
             Source::Stdin => IntoIter::Stdin(io::stdin().lock().lines()),
             Source::Arg(arg) => IntoIter::Arg(Some(arg)),
+            Source::Group {
+                name,
+                entries: None,
+            } => bail!("group {name} not loaded"),
+            Source::Group {
+                entries: Some(entries),
+                ..
+            } => IntoIter::Group(entries.into_iter()),
         })
     }
+
+    /// Parse an input argument, either an `@group:name` reference or an
+    /// inline address, network or range.
+    pub fn from_arg(input: String) -> Self {
+        match input.strip_prefix(GROUP_PREFIX) {
+            Some(name) => Self::group(name),
+            None => Self::Arg(input),
+        }
+    }
+
+    fn group(name: &str) -> Self {
+        Self::Group {
+            name: name.to_owned(),
+            entries: None,
+        }
+    }
+
+    /// Parse a list option value, either a file path ('-' for stdin) or an
+    /// inline address, network or range.
+    pub fn from_file_or_arg(input: &str) -> Result<Self, Infallible> {
+        if input.parse::<AddrOrNet>().is_ok() {
+            Ok(Self::Arg(input.to_owned()))
+        } else {
+            Self::from_str(input)
+        }
+    }
 }
 
 impl FromStr for Source {
@@ -31,26 +76,165 @@ impl FromStr for Source {
     fn from_str(input: &str) -> Result<Self, Self::Err> {
         Ok(if input == "-" {
             Self::Stdin
+        } else if let Some(name) = input.strip_prefix(GROUP_PREFIX) {
+            Self::group(name)
         } else {
             Self::File(PathBuf::from_str(input)?)
         })
     }
 }
 
+impl Display for Source {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::File(path) => write!(f, "{}", path.display()),
+            Source::Stdin => write!(f, "(standard input)"),
+            Source::Arg(_) => write!(f, "(argument)"),
+            Source::Group { name, .. } => write!(f, "{GROUP_PREFIX}{name}"),
+        }
+    }
+}
+
+/// Load the entries of the group sources from the configuration.
+pub fn resolve_groups(
+    sources: &mut [Source],
+    mut configuration: Option<&mut Config>,
+) -> Result<(), AnyError> {
+    for source in sources {
+        if let Source::Group { name, entries } = source
+            && entries.is_none()
+        {
+            *entries = Some(group::group_entries(name, configuration.as_deref_mut())?.to_vec());
+        }
+    }
+    Ok(())
+}
+
+/// Entry read from a source.
+pub enum Line {
+    Text(String),
+    /// Group entry, never parsed again.
+    Entry(AddrOrNet),
+}
+
+impl Line {
+    pub fn is_blank(&self) -> bool {
+        matches!(self, Line::Text(text) if text.trim().is_empty())
+    }
+
+    pub fn parse<T: InputValue>(&self) -> Result<T, AnyError> {
+        match self {
+            Line::Text(text) => T::from_str(text.trim()).map_err(Into::into),
+            Line::Entry(entry) => T::from_entry(*entry),
+        }
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Line::Text(text) => write!(f, "{text}"),
+            Line::Entry(entry) => write!(f, "{entry}"),
+        }
+    }
+}
+
 pub enum IntoIter {
     File(Lines<BufReader<File>>),
     Stdin(Lines<StdinLock<'static>>),
     Arg(Option<String>),
+    Group(std::vec::IntoIter<AddrOrNet>),
 }
 
 impl Iterator for IntoIter {
-    type Item = Result<String, AnyError>;
+    type Item = Result<Line, AnyError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         match self {
-            IntoIter::File(reader) => reader.next().map(|l| l.map_err(AnyError::from)),
-            IntoIter::Stdin(lock) => lock.next().map(|l| l.map_err(AnyError::from)),
-            IntoIter::Arg(arg) => arg.take().map(Ok),
+            IntoIter::File(reader) => reader.next().map(|l| Ok(Line::Text(l?))),
+            IntoIter::Stdin(lock) => lock.next().map(|l| Ok(Line::Text(l?))),
+            IntoIter::Arg(arg) => arg.take().map(|arg| Ok(Line::Text(arg))),
+            IntoIter::Group(entries) => entries.next().map(|entry| Ok(Line::Entry(entry))),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Source, resolve_groups};
+    use crate::{addr_or_net::AddrOrNet, config::Config, input::Input};
+
+    #[test]
+    fn parse() {
+        let group = |source: Source| match source {
+            Source::Group {
+                name,
+                entries: None,
+            } => Some(name),
+            _ => None,
+        };
+        assert_eq!(
+            group(Source::from_arg("@group:office".to_owned())).as_deref(),
+            Some("office")
+        );
+        assert_eq!(
+            group("@group:office".parse().unwrap()).as_deref(),
+            Some("office")
+        );
+        assert_eq!(
+            group(Source::from_file_or_arg("@group:office").unwrap()).as_deref(),
+            Some("office")
+        );
+        assert!(matches!(
+            Source::from_arg("10.0.0.1".to_owned()),
+            Source::Arg(_)
+        ));
+        assert!(matches!(
+            Source::from_file_or_arg("10.0.0.0/8").unwrap(),
+            Source::Arg(_)
+        ));
+        assert!(matches!("-".parse().unwrap(), Source::Stdin));
+        assert!(matches!("group.txt".parse().unwrap(), Source::File(_)));
+    }
+
+    #[test]
+    fn resolve() {
+        let mut configuration = toml::from_str::<Config>(
+            r#"
+            [[groups]]
+            name = "office"
+            nets = ["10.0.0.1", "10.0.1.0/24", "10.0.2.1-10.0.2.5"]
+            "#,
+        )
+        .unwrap();
+        let sources = || {
+            vec![
+                Source::from_arg("@group:office".to_owned()),
+                Source::from_arg("10.9.0.0/16".to_owned()),
+            ]
+        };
+        assert!(sources().remove(0).into_iter().is_err());
+
+        let mut resolved = sources();
+        resolve_groups(&mut resolved, Some(&mut configuration)).unwrap();
+        // Entries as written, not expanded to networks.
+        assert_eq!(
+            Input::<AddrOrNet>::Lazy(resolved)
+                .into_iter()
+                .map(|value| value.unwrap().to_string())
+                .collect::<Vec<_>>(),
+            [
+                "10.0.0.1",
+                "10.0.1.0/24",
+                "10.0.2.1-10.0.2.5",
+                "10.9.0.0/16"
+            ]
+        );
+
+        let mut unknown = vec![Source::from_arg("@group:unknown".to_owned())];
+        assert!(resolve_groups(&mut unknown, Some(&mut configuration)).is_err());
+        assert!(resolve_groups(&mut sources(), None).is_err());
+        // Nothing to load without group sources.
+        assert!(resolve_groups(&mut [Source::Stdin], None).is_ok());
+    }
+}
@@ -0,0 +1,180 @@
+use std::sync::OnceLock;
+
+use ipnet::IpNet;
+use strum::IntoStaticStr;
+
+use crate::{addr_or_net::AddrOrNet, ip_range::IpRange};
+
+/// Coarse classification of a block.
+#[derive(IntoStaticStr, Eq, PartialEq, Copy, Clone, Debug)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Scope {
+    Unspecified,
+    Loopback,
+    LinkLocal,
+    Private,
+    Shared,
+    UniqueLocal,
+    Documentation,
+    Benchmarking,
+    Multicast,
+    Broadcast,
+    Translation,
+    Tunnel,
+    Protocol,
+    Reserved,
+    Global,
+}
+
+/// Entry of the IANA special-purpose address registries.
+#[derive(Debug)]
+pub struct Block {
+    pub net: IpNet,
+    pub name: &'static str,
+    pub rfc: &'static str,
+    /// Unspecified for deprecated or not applicable entries.
+    pub globally_reachable: Option<bool>,
+    pub scope: Scope,
+}
+
+type Entry = (
+    &'static str,
+    &'static str,
+    &'static str,
+    Option<bool>,
+    Scope,
+);
+
+// https://www.iana.org/assignments/iana-ipv4-special-registry
+// https://www.iana.org/assignments/iana-ipv6-special-registry
+// Multicast and IPv6 global unicast blocks come from the address space
+// registries, as they are what users usually look for.
+#[rustfmt::skip]
+const ENTRIES: &[Entry] = &[
+    ("0.0.0.0/8", "This network", "RFC 791", Some(false), Scope::Unspecified),
+    ("0.0.0.0/32", "This host on this network", "RFC 1122", Some(false), Scope::Unspecified),
+    ("10.0.0.0/8", "Private-Use", "RFC 1918", Some(false), Scope::Private),
+    ("100.64.0.0/10", "Shared Address Space", "RFC 6598", Some(false), Scope::Shared),
+    ("127.0.0.0/8", "Loopback", "RFC 1122", Some(false), Scope::Loopback),
+    ("169.254.0.0/16", "Link Local", "RFC 3927", Some(false), Scope::LinkLocal),
+    ("172.16.0.0/12", "Private-Use", "RFC 1918", Some(false), Scope::Private),
+    ("192.0.0.0/24", "IETF Protocol Assignments", "RFC 6890", Some(false), Scope::Protocol),
+    ("192.0.0.0/29", "IPv4 Service Continuity Prefix", "RFC 7335", Some(false), Scope::Translation),
+    ("192.0.0.8/32", "IPv4 dummy address", "RFC 7600", Some(false), Scope::Protocol),
+    ("192.0.0.9/32", "Port Control Protocol Anycast", "RFC 7723", Some(true), Scope::Protocol),
+    ("192.0.0.10/32", "Traversal Using Relays around NAT Anycast", "RFC 8155", Some(true), Scope::Protocol),
+    ("192.0.0.170/32", "NAT64/DNS64 Discovery", "RFC 8880, RFC 7050", Some(false), Scope::Translation),
+    ("192.0.0.171/32", "NAT64/DNS64 Discovery", "RFC 8880, RFC 7050", Some(false), Scope::Translation),
+    ("192.0.2.0/24", "Documentation (TEST-NET-1)", "RFC 5737", Some(false), Scope::Documentation),
+    ("192.31.196.0/24", "AS112-v4", "RFC 7535", Some(true), Scope::Global),
+    ("192.52.193.0/24", "AMT", "RFC 7450", Some(true), Scope::Global),
+    ("192.88.99.0/24", "Deprecated (6to4 Relay Anycast)", "RFC 7526", None, Scope::Tunnel),
+    ("192.88.99.2/32", "6a44-relay anycast address", "RFC 6751", Some(false), Scope::Tunnel),
+    ("192.168.0.0/16", "Private-Use", "RFC 1918", Some(false), Scope::Private),
+    ("192.175.48.0/24", "Direct Delegation AS112 Service", "RFC 7534", Some(true), Scope::Global),
+    ("198.18.0.0/15", "Benchmarking", "RFC 2544", Some(false), Scope::Benchmarking),
+    ("198.51.100.0/24", "Documentation (TEST-NET-2)", "RFC 5737", Some(false), Scope::Documentation),
+    ("203.0.113.0/24", "Documentation (TEST-NET-3)", "RFC 5737", Some(false), Scope::Documentation),
+    ("224.0.0.0/4", "Multicast", "RFC 5771", None, Scope::Multicast),
+    ("240.0.0.0/4", "Reserved", "RFC 1112", Some(false), Scope::Reserved),
+    ("255.255.255.255/32", "Limited Broadcast", "RFC 8190, RFC 919", Some(false), Scope::Broadcast),
+    ("::/128", "Unspecified Address", "RFC 4291", Some(false), Scope::Unspecified),
+    ("::1/128", "Loopback Address", "RFC 4291", Some(false), Scope::Loopback),
+    ("::ffff:0:0/96", "IPv4-mapped Address", "RFC 4291", Some(false), Scope::Translation),
+    ("64:ff9b::/96", "IPv4-IPv6 Translat.", "RFC 6052", Some(true), Scope::Translation),
+    ("64:ff9b:1::/48", "IPv4-IPv6 Translat.", "RFC 8215", Some(false), Scope::Translation),
+    ("100::/64", "Discard-Only Address Block", "RFC 6666", Some(false), Scope::Reserved),
+    ("100:0:0:1::/64", "Dummy IPv6 Prefix", "RFC 9780", Some(false), Scope::Reserved),
+    ("2000::/3", "Global Unicast", "RFC 4291", Some(true), Scope::Global),
+    ("2001::/23", "IETF Protocol Assignments", "RFC 2928", Some(false), Scope::Protocol),
+    ("2001::/32", "TEREDO", "RFC 4380, RFC 8190", None, Scope::Tunnel),
+    ("2001:1::1/128", "Port Control Protocol Anycast", "RFC 7723", Some(true), Scope::Protocol),
+    ("2001:1::2/128", "Traversal Using Relays around NAT Anycast", "RFC 8155", Some(true), Scope::Protocol),
+    ("2001:1::3/128", "DNS-SD Service Registration Protocol Anycast", "RFC 9665", Some(true), Scope::Protocol),
+    ("2001:2::/48", "Benchmarking", "RFC 5180", Some(false), Scope::Benchmarking),
+    ("2001:3::/32", "AMT", "RFC 7450", Some(true), Scope::Global),
+    ("2001:4:112::/48", "AS112-v6", "RFC 7535", Some(true), Scope::Global),
+    ("2001:10::/28", "Deprecated (previously ORCHID)", "RFC 4843", None, Scope::Reserved),
+    ("2001:20::/28", "ORCHIDv2", "RFC 7343", Some(true), Scope::Global),
+    ("2001:30::/28", "Drone Remote ID Protocol Entity Tags (DETs) Prefix", "RFC 9374", Some(true), Scope::Global),
+    ("2001:db8::/32", "Documentation", "RFC 3849", Some(false), Scope::Documentation),
+    ("2002::/16", "6to4", "RFC 3056", None, Scope::Tunnel),
+    ("2620:4f:8000::/48", "Direct Delegation AS112 Service", "RFC 7534", Some(true), Scope::Global),
+    ("3fff::/20", "Documentation", "RFC 9637", Some(false), Scope::Documentation),
+    ("5f00::/16", "Segment Routing (SRv6) SIDs", "RFC 9602", Some(false), Scope::Reserved),
+    ("fc00::/7", "Unique-Local", "RFC 4193, RFC 8190", Some(false), Scope::UniqueLocal),
+    ("fe80::/10", "Link-Local Unicast", "RFC 4291", Some(false), Scope::LinkLocal),
+    ("ff00::/8", "Multicast", "RFC 4291", None, Scope::Multicast),
+];
+
+/// IPv4 addresses outside of any special-purpose block.
+const IPV4_GLOBAL_UNICAST: Entry = (
+    "0.0.0.0/0",
+    "Global Unicast",
+    "RFC 791",
+    Some(true),
+    Scope::Global,
+);
+
+fn blocks() -> &'static (Vec<Block>, Block) {
+    static CELL: OnceLock<(Vec<Block>, Block)> = OnceLock::new();
+    CELL.get_or_init(|| {
+        let block = |(net, name, rfc, globally_reachable, scope): Entry| Block {
+            net: net.parse().expect("invalid special-purpose block"),
+            name,
+            rfc,
+            globally_reachable,
+            scope,
+        };
+        (
+            ENTRIES.iter().copied().map(block).collect(),
+            block(IPV4_GLOBAL_UNICAST),
+        )
+    })
+}
+
+/// Most specific registry block containing the whole input, if any.
+pub fn classify(input: AddrOrNet) -> Option<&'static Block> {
+    let (blocks, ipv4_global_unicast) = blocks();
+    let range = input.range();
+    let mut matching = blocks
+        .iter()
+        .filter(|block| IpRange::from(block.net).contains(&range))
+        .peekable();
+    if matching.peek().is_none() {
+        // IPv4 has no global unicast block: any input not touching a special-purpose
+        // block is one.
+        return (input.is_ipv4()
+            && !blocks
+                .iter()
+                .any(|block| IpRange::from(block.net).overlaps(&range)))
+        .then_some(ipv4_global_unicast);
+    }
+    matching.max_by_key(|block| block.net.prefix_len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Scope, classify};
+
+    #[test]
+    fn most_specific() {
+        let scope = |input: &str| classify(input.parse().unwrap()).map(|b| b.scope);
+        assert_eq!(scope("10.1.2.3"), Some(Scope::Private));
+        assert_eq!(scope("100.64.0.0/10"), Some(Scope::Shared));
+        assert_eq!(scope("192.0.0.9"), Some(Scope::Protocol));
+        assert_eq!(scope("192.0.0.1"), Some(Scope::Translation));
+        assert_eq!(scope("8.8.8.8"), Some(Scope::Global));
+        assert_eq!(scope("10.0.0.0/7"), None);
+        assert_eq!(scope("192.168.0.1-192.168.0.255"), Some(Scope::Private));
+        assert_eq!(scope("2001::1"), Some(Scope::Tunnel));
+        assert_eq!(scope("2001:db8::/48"), Some(Scope::Documentation));
+        assert_eq!(scope("2a00::1"), Some(Scope::Global));
+        assert_eq!(scope("fd00::1"), Some(Scope::UniqueLocal));
+        assert_eq!(scope("4000::1"), None);
+        assert_eq!(
+            classify("::1".parse().unwrap()).unwrap().name,
+            "Loopback Address"
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::str::FromStr;
+
+use anyhow::{Error as AnyError, bail};
+use evalexpr::Value as ExprValue;
+use itertools::Itertools;
+
+use crate::{
+    addr_or_net::AddrOrNet,
+    commands::{filter::Placeholder, info::Field},
+    config::Config,
+    group::GroupMatch,
+    output::{COLUMNS, Record},
+};
+
+/// Line rendered for each result, `{name}` being replaced by the matching
+/// result column, info field or filter placeholder (`{{` and `}}` for literal
+/// braces).
+#[derive(Clone, Debug)]
+pub struct Template {
+    segments: Vec<Segment>,
+}
+
+#[derive(Clone, Debug)]
+enum Segment {
+    Text(String),
+    /// Field or placeholder, `column` telling whether a result column of the
+    /// same name takes precedence.
+    Field {
+        field: Field,
+        column: bool,
+    },
+    Placeholder {
+        placeholder: Placeholder,
+        column: bool,
+    },
+    Column(String),
+}
+
+impl Template {
+    /// Whether rendering requires the configuration groups.
+    pub fn uses_groups(&self) -> bool {
+        self.segments.iter().any(|segment| {
+            matches!(
+                segment,
+                Segment::Placeholder {
+                    placeholder: Placeholder::Group | Placeholder::Groups,
+                    ..
+                }
+            )
+        })
+    }
+
+    pub fn render(
+        &self,
+        entry: Option<AddrOrNet>,
+        record: impl FnOnce() -> Record,
+        mut configuration: Option<&mut Config>,
+    ) -> Result<String, AnyError> {
+        let mut record = Some(record);
+        let mut columns = None::<Record>;
+        let mut buffer = String::with_capacity(128);
+        for segment in &self.segments {
+            let (name, column) = match segment {
+                Segment::Text(text) => {
+                    buffer.push_str(text);
+                    continue;
+                }
+                Segment::Field { field, column } => (<&str>::from(field), *column),
+                Segment::Placeholder {
+                    placeholder,
+                    column,
+                } => (<&str>::from(placeholder), *column),
+                Segment::Column(name) => (name.as_str(), true),
+            };
+            if column {
+                // Columns are only built when needed.
+                let columns = columns.get_or_insert_with(|| record.take().unwrap()());
+                if let Some((_, value)) = columns.iter().find(|(key, _)| *key == name) {
+                    buffer.push_str(&value.to_string());
+                    continue;
+                }
+            }
+            match (segment, entry) {
+                (Segment::Field { field, .. }, Some(entry)) => {
+                    if let Some(value) = field.value(entry) {
+                        buffer.push_str(&value.to_string());
+                    }
+                }
+                (Segment::Placeholder { placeholder, .. }, Some(entry)) => {
+                    buffer.push_str(&to_text(placeholder.resolve(
+                        entry,
+                        configuration.as_deref_mut(),
+                        GroupMatch::First,
+                    )?))
+                }
+                _ => bail!("template placeholder not available for this command: {name}"),
+            }
+        }
+        Ok(buffer)
+    }
+}
+
+impl FromStr for Template {
+    type Err = AnyError;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut segments = Vec::new();
+        let mut text = String::new();
+        let mut chars = input.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let Some((name, rest)) = chars.as_str().split_once('}') else {
+                        bail!("unterminated template placeholder");
+                    };
+                    let name = name.trim();
+                    if name.is_empty() {
+                        bail!("empty template placeholder");
+                    }
+                    chars = rest.chars();
+                    if !text.is_empty() {
+                        segments.push(Segment::Text(std::mem::take(&mut text)));
+                    }
+                    let column = COLUMNS.contains(&name);
+                    segments.push(if let Ok(field) = name.parse() {
+                        Segment::Field { field, column }
+                    } else if let Ok(placeholder) = name.parse() {
+                        Segment::Placeholder {
+                            placeholder,
+                            column,
+                        }
+                    } else if column {
+                        Segment::Column(name.to_owned())
+                    } else {
+                        bail!("unknown template placeholder: {name}");
+                    });
+                }
+                '}' => bail!("unexpected '}}' in template (use '}}}}' for a literal one)"),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            segments.push(Segment::Text(text));
+        }
+        Ok(Self { segments })
+    }
+}
+
+fn to_text(value: ExprValue) -> String {
+    match value {
+        ExprValue::String(value) => value,
+        ExprValue::Empty => String::new(),
+        ExprValue::Tuple(values) => values.into_iter().map(to_text).join(","),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Template;
+    use crate::output::Value;
+
+    #[test]
+    fn render() {
+        let render = |template: &str, entry: &str| {
+            template
+                .parse::<Template>()
+                .unwrap()
+                .render(
+                    Some(entry.parse().unwrap()),
+                    || vec![("input", Value::string("input"))],
+                    None,
+                )
+                .unwrap()
+        };
+        assert_eq!(
+            render(
+                "allow from {network} mask {net_mask}; # {input}",
+                "10.0.0.0/24"
+            ),
+            "allow from 10.0.0.0 mask 255.255.255.0; # input"
+        );
+        assert_eq!(
+            render("{{{network}/{prefix}}} {type}", "10.0.0.0/8"),
+            "{10.0.0.0/8} net"
+        );
+        assert_eq!(render("{prefix}{network}", "10.0.0.1-2"), "30");
+        // The command's own column wins over the filter placeholder.
+        assert_eq!(
+            "{input} {group}"
+                .parse::<Template>()
+                .unwrap()
+                .render(
+                    Some("10.1.2.3".parse().unwrap()),
+                    || {
+                        vec![
+                            ("input", Value::string("10.1.2.3")),
+                            ("group", Value::string("dc1")),
+                        ]
+                    },
+                    None,
+                )
+                .unwrap(),
+            "10.1.2.3 dc1"
+        );
+        assert!("{network".parse::<Template>().is_err());
+        assert!("network}".parse::<Template>().is_err());
+        assert!("{unknown}".parse::<Template>().is_err());
+        assert!(
+            "{count}"
+                .parse::<Template>()
+                .unwrap()
+                .render(None, Vec::new, None)
+                .is_err()
+        );
+    }
+}